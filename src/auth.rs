@@ -0,0 +1,161 @@
+use futures::future::BoxFuture;
+
+mod authorized_user;
+mod external_account;
+mod impersonated;
+mod metadata_server;
+mod service_account;
+
+pub use authorized_user::{AuthorizedUser, AuthorizedUserCredentials};
+pub use external_account::{
+    CredentialSource, ExecutableSource, ExternalAccount, ExternalAccountCredentials,
+    SubjectTokenFormat,
+};
+pub use impersonated::ImpersonatedCredentials;
+pub use metadata_server::MetadataServer;
+pub use service_account::ServiceAccountCredentials;
+
+/// The file named by the `SERVICE_ACCOUNT` or `GOOGLE_APPLICATION_CREDENTIALS` environment
+/// variable, if either is set.
+pub(crate) fn credentials_path() -> Option<String> {
+    std::env::var("SERVICE_ACCOUNT")
+        .or_else(|_| std::env::var("GOOGLE_APPLICATION_CREDENTIALS"))
+        .ok()
+}
+
+/// Reads a credentials file, reporting failures as `Error::Credentials`.
+pub(crate) fn read_credentials(path: &std::path::Path) -> crate::Result<String> {
+    std::fs::read_to_string(path).map_err(|e| {
+        crate::Error::Credentials(format!(
+            "cannot read credentials from {}: {}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// The scope of the access tokens requested by the token providers in this module.
+pub(crate) const DEVSTORAGE_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.full_control";
+
+/// The scope required to call the IAM Credentials API, for example to impersonate a service
+/// account.
+pub const IAM_SCOPE: &str = "https://www.googleapis.com/auth/iam";
+
+/// A source of the OAuth2 access tokens that authenticate the requests of a `Client`. Every
+/// request asks the provider for a token, so providers are expected to cache their tokens and
+/// only fetch a new one when the cached token is about to expire. The providers in this module
+/// refresh their token five minutes before it expires, which can be changed with their
+/// `with_refresh_margin` method, and never make requests wait for a refresh while the current
+/// token is still valid.
+///
+/// This crate provides token providers for service account keys, the `authorized_user`
+/// credentials created by `gcloud auth application-default login`, `external_account` credentials
+/// for workload identity federation, the metadata server of Google
+/// Compute Engine and Google Kubernetes Engine, impersonation of a service account, a static token,
+/// and anonymous access.
+/// ### Example
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use cloud_storage::{auth::MetadataServer, Client};
+///
+/// let client = Client::with_token_provider(MetadataServer::new()).with_project_id("my-project");
+/// let buckets = client.bucket().list().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait TokenProvider: Send + Sync {
+    /// Returns the access token that is sent in the `Authorization` header of a request, or `None`
+    /// if requests should not be authenticated. `client` can be used to fetch a new token.
+    fn token<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>>;
+
+    /// The project the credentials belong to, if they specify one. A `Client` that is constructed
+    /// from this provider creates and lists `Bucket`s and `HmacKey`s in this project, unless
+    /// another project is set with `Client::with_project_id`.
+    fn project_id(&self) -> Option<&str> {
+        None
+    }
+
+    /// The email address of the service account the tokens belong to, if they belong to one that
+    /// is known. `UrlSigner::sign_blob` signs urls as this service account.
+    fn service_account_email<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async { Ok(None) })
+    }
+}
+
+/// A token provider that always returns the same access token, for example one obtained with
+/// `gcloud auth print-access-token`. The token is not refreshed, so requests fail once it expires.
+#[derive(Debug, Clone)]
+pub struct StaticToken(String);
+
+impl StaticToken {
+    /// Creates a token provider that authenticates every request with `token`.
+    pub fn new(token: impl Into<String>) -> Self {
+        Self(token.into())
+    }
+}
+
+impl TokenProvider for StaticToken {
+    fn token<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move { Ok(Some(self.0.clone())) })
+    }
+}
+
+/// A token provider that does not authenticate requests, which suffices to read from public
+/// buckets and to talk to emulators.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Anonymous;
+
+impl TokenProvider for Anonymous {
+    fn token<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async { Ok(None) })
+    }
+}
+
+/// A token provider for credentials that could not be loaded, which fails every request with the
+/// reason they could not be loaded.
+pub(crate) struct Unavailable(pub(crate) String);
+
+impl TokenProvider for Unavailable {
+    fn token<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move { Err(crate::Error::Credentials(self.0.clone())) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Response, TestServer};
+
+    #[tokio::test]
+    async fn static_token_and_anonymous() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|_| {
+            Response::new(200).json(serde_json::json!({ "kind": "storage#buckets" }))
+        });
+        let client = crate::Client::with_token_provider(StaticToken::new("secret"))
+            .with_endpoints(crate::client::Endpoints::emulator(server.url()))
+            .with_project_id("test-project");
+        client.bucket().list().await?;
+        server.client().bucket().list().await?;
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("Authorization"), Some("Bearer secret"));
+        assert_eq!(requests[1].header("Authorization"), None);
+        Ok(())
+    }
+}
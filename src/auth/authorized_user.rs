@@ -0,0 +1,165 @@
+use super::{TokenProvider, DEVSTORAGE_SCOPE};
+use crate::token::{Token, TokenResponse};
+use futures::future::BoxFuture;
+
+/// The credentials of a user, as stored by `gcloud auth application-default login` in
+/// `application_default_credentials.json`.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct AuthorizedUser {
+    /// The OAuth2 client the refresh token was issued to.
+    pub client_id: String,
+    /// The secret of the OAuth2 client.
+    pub client_secret: String,
+    /// The refresh token that is exchanged for access tokens.
+    pub refresh_token: String,
+    /// The project that is billed for requests made with these credentials, if any.
+    pub quota_project_id: Option<String>,
+}
+
+/// A token provider that exchanges the refresh token of an `AuthorizedUser` for access tokens.
+pub struct AuthorizedUserCredentials {
+    user: AuthorizedUser,
+    token_url: String,
+    token_cache: Token,
+}
+
+impl AuthorizedUserCredentials {
+    /// Creates a token provider that authenticates as `user`.
+    pub fn new(user: AuthorizedUser) -> Self {
+        Self {
+            user,
+            token_url: "https://oauth2.googleapis.com/token".to_string(),
+            token_cache: Token::new(DEVSTORAGE_SCOPE),
+        }
+    }
+
+    /// Reads the credentials that `gcloud auth application-default login` stores in
+    /// `application_default_credentials.json`, in the directory given by the `CLOUDSDK_CONFIG`
+    /// environment variable or else the default gcloud configuration directory.
+    pub fn from_well_known_file() -> crate::Result<Self> {
+        let path = well_known_file().ok_or_else(|| {
+            crate::Error::Credentials("the gcloud configuration directory is unknown".to_string())
+        })?;
+        let json = super::read_credentials(&path)?;
+        let user = serde_json::from_str(&json).map_err(|e| {
+            crate::Error::Credentials(format!("the authorized user is not valid: {}", e))
+        })?;
+        Ok(Self::new(user))
+    }
+
+    /// Replaces the url where the refresh token is exchanged for an access token. Defaults to
+    /// `https://oauth2.googleapis.com/token`.
+    pub fn with_token_url(mut self, token_url: impl Into<String>) -> Self {
+        self.token_url = token_url.into();
+        self
+    }
+
+    /// Refreshes the access token `margin` before it expires. Defaults to five minutes.
+    pub fn with_refresh_margin(mut self, margin: std::time::Duration) -> Self {
+        self.token_cache = self.token_cache.with_refresh_margin(margin);
+        self
+    }
+
+    async fn refresh(&self, client: &reqwest::Client) -> crate::Result<(String, u64)> {
+        let body = [
+            ("grant_type", "refresh_token"),
+            ("client_id", &self.user.client_id),
+            ("client_secret", &self.user.client_secret),
+            ("refresh_token", &self.user.refresh_token),
+        ];
+        let response = client.post(&self.token_url).form(&body).send().await?;
+        Ok(TokenResponse::parse(response).await?.into_token())
+    }
+}
+
+impl TokenProvider for AuthorizedUserCredentials {
+    fn token<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move {
+            let token = self
+                .token_cache
+                .get_or_refresh(|_| self.refresh(client))
+                .await?;
+            Ok(Some(token))
+        })
+    }
+
+    fn project_id(&self) -> Option<&str> {
+        self.user.quota_project_id.as_deref()
+    }
+}
+
+// The location of `application_default_credentials.json`.
+fn well_known_file() -> Option<std::path::PathBuf> {
+    let config_dir = match std::env::var_os("CLOUDSDK_CONFIG") {
+        Some(dir) => std::path::PathBuf::from(dir),
+        None if cfg!(windows) => {
+            std::path::PathBuf::from(std::env::var_os("APPDATA")?).join("gcloud")
+        }
+        None => std::path::PathBuf::from(std::env::var_os("HOME")?)
+            .join(".config")
+            .join("gcloud"),
+    };
+    Some(config_dir.join("application_default_credentials.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Response, TestServer};
+
+    #[tokio::test]
+    async fn exchanges_refresh_token() -> Result<(), Box<dyn std::error::Error>> {
+        let user: AuthorizedUser = serde_json::from_value(serde_json::json!({
+            "type": "authorized_user",
+            "client_id": "client.apps.googleusercontent.com",
+            "client_secret": "secret",
+            "refresh_token": "1//refresh",
+        }))?;
+        let server = TestServer::start(|_| {
+            Response::new(200).json(serde_json::json!({
+                "access_token": "ya29.user",
+                "expires_in": 3599,
+                "token_type": "Bearer",
+            }))
+        });
+        let credentials =
+            AuthorizedUserCredentials::new(user).with_token_url(format!("{}/token", server.url()));
+        let client = reqwest::Client::new();
+        assert_eq!(
+            credentials.token(&client).await?.as_deref(),
+            Some("ya29.user")
+        );
+        credentials.token(&client).await?;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            String::from_utf8(requests[0].body.clone())?,
+            "grant_type=refresh_token&client_id=client.apps.googleusercontent.com\
+             &client_secret=secret&refresh_token=1%2F%2Frefresh"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rejected_refresh_token() {
+        let server = TestServer::start(|_| {
+            Response::new(400).json(serde_json::json!({ "error": "invalid_grant" }))
+        });
+        let credentials = AuthorizedUserCredentials::new(AuthorizedUser {
+            client_id: "client".to_string(),
+            client_secret: "secret".to_string(),
+            refresh_token: "revoked".to_string(),
+            quota_project_id: None,
+        })
+        .with_token_url(format!("{}/token", server.url()));
+        let error = credentials
+            .token(&reqwest::Client::new())
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("invalid_grant"), "{}", error);
+    }
+}
@@ -0,0 +1,571 @@
+use super::{impersonated, TokenProvider, DEVSTORAGE_SCOPE};
+use crate::token::{Token, TokenResponse};
+use futures::future::BoxFuture;
+use std::{collections::HashMap, time::Duration};
+
+const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// The configuration of an `external_account` credential, as created by
+/// `gcloud iam workload-identity-pools create-cred-config`. It describes where to find a token
+/// issued by another identity provider, such as GitHub Actions or a Kubernetes cluster, and how to
+/// exchange it for a Google access token using
+/// [workload identity federation](https://cloud.google.com/iam/docs/workload-identity-federation).
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExternalAccount {
+    /// The resource name of the workload identity pool provider, which is the audience of the
+    /// token exchange.
+    pub audience: String,
+    /// The type of the subject token, for example `urn:ietf:params:oauth:token-type:jwt`.
+    pub subject_token_type: String,
+    /// The url of the Security Token Service. Defaults to `https://sts.googleapis.com/v1/token`.
+    #[serde(default = "default_token_url")]
+    pub token_url: String,
+    /// The url of the `generateAccessToken` method of the service account to impersonate with the
+    /// federated token, if any.
+    pub service_account_impersonation_url: Option<String>,
+    /// Where the subject token is read from.
+    pub credential_source: CredentialSource,
+    /// The project that is billed for requests made with these credentials, if any.
+    pub quota_project_id: Option<String>,
+    /// The project that is billed for the token exchange of a workforce pool, if any.
+    pub workforce_pool_user_project: Option<String>,
+}
+
+fn default_token_url() -> String {
+    "https://sts.googleapis.com/v1/token".to_string()
+}
+
+/// Where the subject token of an `ExternalAccount` is read from. Exactly one of `file`, `url` and
+/// `executable` is set.
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+pub struct CredentialSource {
+    /// A file that contains the subject token.
+    pub file: Option<String>,
+    /// A url that returns the subject token.
+    pub url: Option<String>,
+    /// The headers sent to `url`.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// A command that prints the subject token.
+    pub executable: Option<ExecutableSource>,
+    /// How the subject token is found in the contents of `file` or the response of `url`. When
+    /// left out, the whole text is the token.
+    pub format: Option<SubjectTokenFormat>,
+    /// Set for AWS credential sources, which are not supported.
+    pub environment_id: Option<String>,
+}
+
+/// How the subject token is found in a file or response.
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct SubjectTokenFormat {
+    /// Either `text`, when the whole text is the token, or `json`.
+    #[serde(rename = "type")]
+    pub r#type: String,
+    /// The field of the JSON object that contains the token, when `type` is `json`.
+    pub subject_token_field_name: Option<String>,
+}
+
+/// A command that prints the subject token in the
+/// [executable response format](https://cloud.google.com/iam/docs/workload-identity-federation-with-other-providers#use_executable-sourced_credentials_with_oidc_and_saml).
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct ExecutableSource {
+    /// The command line to run, split on whitespace.
+    pub command: String,
+    /// How long the command may run, in milliseconds. Defaults to 30 seconds.
+    pub timeout_millis: Option<u64>,
+    /// A file in which the command caches its response. A response in this file is used instead
+    /// of running the command until it expires.
+    pub output_file: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ExecutableResponse {
+    success: bool,
+    token_type: Option<String>,
+    id_token: Option<String>,
+    saml_response: Option<String>,
+    expiration_time: Option<u64>,
+    code: Option<String>,
+    message: Option<String>,
+}
+
+/// A token provider that exchanges the subject token of an `ExternalAccount` for a Google access
+/// token at the Security Token Service, and then optionally impersonates a service account.
+///
+/// Credential sources that run an executable are only used when the
+/// `GOOGLE_EXTERNAL_ACCOUNT_ALLOW_EXECUTABLES` environment variable is set to `1`.
+pub struct ExternalAccountCredentials {
+    account: ExternalAccount,
+    allow_executables: bool,
+    token_cache: Token,
+}
+
+impl ExternalAccountCredentials {
+    /// Creates a token provider that authenticates with `account`.
+    pub fn new(account: ExternalAccount) -> Self {
+        Self {
+            account,
+            allow_executables: matches!(
+                std::env::var("GOOGLE_EXTERNAL_ACCOUNT_ALLOW_EXECUTABLES").as_deref(),
+                Ok("1")
+            ),
+            token_cache: Token::new(DEVSTORAGE_SCOPE),
+        }
+    }
+
+    /// Replaces the url of the Security Token Service.
+    pub fn with_token_url(mut self, token_url: impl Into<String>) -> Self {
+        self.account.token_url = token_url.into();
+        self
+    }
+
+    /// Replaces the url of the `generateAccessToken` method of the service account to
+    /// impersonate.
+    pub fn with_service_account_impersonation_url(mut self, url: impl Into<String>) -> Self {
+        self.account.service_account_impersonation_url = Some(url.into());
+        self
+    }
+
+    /// Exchanges a new subject token `margin` before the current access token expires. Defaults to
+    /// five minutes.
+    pub fn with_refresh_margin(mut self, margin: Duration) -> Self {
+        self.token_cache = self.token_cache.with_refresh_margin(margin);
+        self
+    }
+
+    async fn refresh(&self, client: &reqwest::Client, scope: &str) -> crate::Result<(String, u64)> {
+        let subject_token = self.subject_token(client).await?;
+        let impersonation_url = self.account.service_account_impersonation_url.as_ref();
+        let sts_scope = match impersonation_url {
+            Some(_) => CLOUD_PLATFORM_SCOPE,
+            None => scope,
+        };
+        let mut body = vec![
+            (
+                "grant_type",
+                "urn:ietf:params:oauth:grant-type:token-exchange",
+            ),
+            ("audience", &self.account.audience),
+            ("scope", sts_scope),
+            (
+                "requested_token_type",
+                "urn:ietf:params:oauth:token-type:access_token",
+            ),
+            ("subject_token", &subject_token),
+            ("subject_token_type", &self.account.subject_token_type),
+        ];
+        let options;
+        if let Some(ref project) = self.account.workforce_pool_user_project {
+            options = serde_json::json!({ "userProject": project }).to_string();
+            body.push(("options", &options));
+        }
+        let response = client
+            .post(&self.account.token_url)
+            .form(&body)
+            .send()
+            .await?;
+        let token = TokenResponse::parse(response).await?.into_token();
+        match impersonation_url {
+            Some(url) => {
+                impersonated::generate_access_token(client, url, &token.0, &[], scope).await
+            }
+            None => Ok(token),
+        }
+    }
+
+    async fn subject_token(&self, client: &reqwest::Client) -> crate::Result<String> {
+        let source = &self.account.credential_source;
+        if source.environment_id.is_some() {
+            return Err(crate::Error::Credentials(
+                "AWS credential sources are not supported".to_string(),
+            ));
+        }
+        if let Some(ref executable) = source.executable {
+            return self.run_executable(executable).await;
+        }
+        let text = if let Some(ref file) = source.file {
+            super::read_credentials(file.as_ref())?
+        } else if let Some(ref url) = source.url {
+            let mut request = client.get(url);
+            for (name, value) in &source.headers {
+                request = request.header(name.as_str(), value.as_str());
+            }
+            let response = request.send().await?;
+            let status = response.status();
+            let text = response.text().await?;
+            if !status.is_success() {
+                return Err(crate::Error::Credentials(format!(
+                    "cannot read the subject token from {}, got {}: {}",
+                    url, status, text
+                )));
+            }
+            text
+        } else {
+            return Err(crate::Error::Credentials(
+                "the credential source has no file, url or executable".to_string(),
+            ));
+        };
+        match source.format {
+            Some(ref format) if format.r#type == "json" => {
+                let field = format.subject_token_field_name.as_deref().unwrap_or("");
+                let json: serde_json::Value = serde_json::from_str(&text)?;
+                json[field].as_str().map(str::to_string).ok_or_else(|| {
+                    crate::Error::Credentials(format!("the subject token has no field `{}`", field))
+                })
+            }
+            _ => Ok(text.trim().to_string()),
+        }
+    }
+
+    async fn run_executable(&self, executable: &ExecutableSource) -> crate::Result<String> {
+        if !self.allow_executables {
+            return Err(crate::Error::Credentials(
+                "executable credential sources require GOOGLE_EXTERNAL_ACCOUNT_ALLOW_EXECUTABLES=1"
+                    .to_string(),
+            ));
+        }
+        if let Some(ref output_file) = executable.output_file {
+            if let Ok(cached) = std::fs::read_to_string(output_file) {
+                if let Ok(response) = serde_json::from_str::<ExecutableResponse>(&cached) {
+                    let unexpired = matches!(
+                        response.expiration_time,
+                        Some(exp) if exp > crate::token::now()
+                    );
+                    if response.success && unexpired {
+                        return self.executable_token(response);
+                    }
+                }
+            }
+        }
+
+        let mut args = executable.command.split_whitespace();
+        let program = args.next().ok_or_else(|| {
+            crate::Error::Credentials("the executable command is empty".to_string())
+        })?;
+        let mut command = std::process::Command::new(program);
+        command
+            .args(args)
+            .stdout(std::process::Stdio::piped())
+            .env("GOOGLE_EXTERNAL_ACCOUNT_AUDIENCE", &self.account.audience)
+            .env(
+                "GOOGLE_EXTERNAL_ACCOUNT_TOKEN_TYPE",
+                &self.account.subject_token_type,
+            )
+            .env("GOOGLE_EXTERNAL_ACCOUNT_INTERACTIVE", "0");
+        if let Some(ref url) = self.account.service_account_impersonation_url {
+            if let Some(email) = impersonated_email(url) {
+                command.env("GOOGLE_EXTERNAL_ACCOUNT_IMPERSONATED_EMAIL", email);
+            }
+        }
+        if let Some(ref output_file) = executable.output_file {
+            command.env("GOOGLE_EXTERNAL_ACCOUNT_OUTPUT_FILE", output_file);
+        }
+        let mut child = command.spawn().map_err(|e| {
+            crate::Error::Credentials(format!("cannot run `{}`: {}", executable.command, e))
+        })?;
+
+        // stdout is drained while the executable runs, otherwise an executable that writes more
+        // than the pipe buffer holds would block until it is killed by the timeout
+        let stdout = child.stdout.take();
+        let reader = tokio::task::spawn_blocking(move || {
+            use std::io::Read;
+            let mut output = String::new();
+            if let Some(mut stdout) = stdout {
+                stdout.read_to_string(&mut output)?;
+            }
+            Ok::<_, std::io::Error>(output)
+        });
+
+        let timeout = Duration::from_millis(executable.timeout_millis.unwrap_or(30_000));
+        let started = std::time::Instant::now();
+        let status = loop {
+            let status = child.try_wait().map_err(|e| {
+                crate::Error::Credentials(format!("cannot run `{}`: {}", executable.command, e))
+            })?;
+            match status {
+                Some(status) => break status,
+                None if started.elapsed() > timeout => {
+                    child.kill().ok();
+                    return Err(crate::Error::Credentials(format!(
+                        "`{}` timed out",
+                        executable.command
+                    )));
+                }
+                None => tokio::time::delay_for(Duration::from_millis(10)).await,
+            }
+        };
+        let output = reader
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|output| output.map_err(|e| e.to_string()))
+            .map_err(|e| {
+                crate::Error::Credentials(format!(
+                    "cannot read the output of the executable: {}",
+                    e
+                ))
+            })?;
+        if !status.success() {
+            return Err(crate::Error::Credentials(format!(
+                "`{}` failed with {}",
+                executable.command, status
+            )));
+        }
+        let response = serde_json::from_str(&output).map_err(|e| {
+            crate::Error::Credentials(format!("the executable response is not valid: {}", e))
+        })?;
+        self.executable_token(response)
+    }
+
+    fn executable_token(&self, response: ExecutableResponse) -> crate::Result<String> {
+        if !response.success {
+            return Err(crate::Error::Credentials(format!(
+                "the executable failed with {}: {}",
+                response.code.unwrap_or_default(),
+                response.message.unwrap_or_default()
+            )));
+        }
+        if let Some(exp) = response.expiration_time {
+            if exp <= crate::token::now() {
+                return Err(crate::Error::Credentials(
+                    "the executable returned an expired token".to_string(),
+                ));
+            }
+        }
+        let token = match response.token_type.as_deref() {
+            Some("urn:ietf:params:oauth:token-type:saml2") => response.saml_response,
+            _ => response.id_token,
+        };
+        token.ok_or_else(|| {
+            crate::Error::Credentials("the executable response contains no token".to_string())
+        })
+    }
+}
+
+// The email address of the service account named in a `generateAccessToken` url.
+fn impersonated_email(url: &str) -> Option<&str> {
+    let (_, email) = url
+        .strip_suffix(":generateAccessToken")?
+        .rsplit_once("/serviceAccounts/")?;
+    Some(email)
+}
+
+impl TokenProvider for ExternalAccountCredentials {
+    fn token<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move {
+            let token = self
+                .token_cache
+                .get_or_refresh(|scope| async move { self.refresh(client, &scope).await })
+                .await?;
+            Ok(Some(token))
+        })
+    }
+
+    fn project_id(&self) -> Option<&str> {
+        self.account.quota_project_id.as_deref()
+    }
+
+    /// The email address of the impersonated service account, if any. Federated tokens do not
+    /// belong to a service account, so urls can only be signed when impersonating one.
+    fn service_account_email<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        let email = self
+            .account
+            .service_account_impersonation_url
+            .as_deref()
+            .and_then(impersonated_email)
+            .map(str::to_string);
+        Box::pin(async move { Ok(email) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Response, TestServer};
+
+    const AUDIENCE: &str = "//iam.googleapis.com/projects/123/locations/global/workloadIdentityPools/ci/providers/github";
+
+    fn sts_server() -> TestServer {
+        TestServer::start(|request| {
+            match request.path.as_str() {
+            "/subject" if request.header("Metadata") == Some("True") => {
+                Response::new(200).json(serde_json::json!({ "value": "oidc-from-url" }))
+            }
+            "/sts" => Response::new(200).json(serde_json::json!({
+                "access_token": "federated",
+                "issued_token_type": "urn:ietf:params:oauth:token-type:access_token",
+                "token_type": "Bearer",
+                "expires_in": 3600,
+            })),
+            "/v1/projects/-/serviceAccounts/ci@my-project.iam.gserviceaccount.com:generateAccessToken"
+                if request.header("Authorization") == Some("Bearer federated") =>
+            {
+                Response::new(200).json(serde_json::json!({
+                    "accessToken": "impersonated",
+                    "expireTime": "2100-01-01T00:00:00Z",
+                }))
+            }
+            _ => Response::new(404).body("not found"),
+        }
+        })
+    }
+
+    fn account(server: &TestServer, credential_source: serde_json::Value) -> ExternalAccount {
+        serde_json::from_value(serde_json::json!({
+            "type": "external_account",
+            "audience": AUDIENCE,
+            "subject_token_type": "urn:ietf:params:oauth:token-type:jwt",
+            "token_url": format!("{}/sts", server.url()),
+            "credential_source": credential_source,
+        }))
+        .unwrap()
+    }
+
+    fn form(request: &crate::test_server::Request) -> HashMap<String, String> {
+        let body = String::from_utf8(request.body.clone()).unwrap();
+        body.split('&')
+            .map(|pair| {
+                let mut parts = pair.splitn(2, '=').map(|part| {
+                    let part = part.replace('+', " ");
+                    percent_encoding::percent_decode_str(&part)
+                        .decode_utf8()
+                        .unwrap()
+                        .into_owned()
+                });
+                (parts.next().unwrap(), parts.next().unwrap_or_default())
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn exchanges_token_from_file() -> Result<(), Box<dyn std::error::Error>> {
+        let server = sts_server();
+        let path = std::env::temp_dir().join("cloud-storage-rs-subject-token");
+        std::fs::write(&path, "oidc-from-file\n")?;
+        let credentials =
+            ExternalAccountCredentials::new(account(&server, serde_json::json!({ "file": path })));
+        let token = credentials.token(&reqwest::Client::new()).await;
+        std::fs::remove_file(&path)?;
+        assert_eq!(token?.as_deref(), Some("federated"));
+
+        let requests = server.requests();
+        let form = form(&requests[0]);
+        assert_eq!(
+            form["grant_type"],
+            "urn:ietf:params:oauth:grant-type:token-exchange"
+        );
+        assert_eq!(form["audience"], AUDIENCE);
+        assert_eq!(form["subject_token"], "oidc-from-file");
+        assert_eq!(form["scope"], DEVSTORAGE_SCOPE);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn exchanges_token_from_url_and_impersonates() -> Result<(), Box<dyn std::error::Error>> {
+        let server = sts_server();
+        let source = serde_json::json!({
+            "url": format!("{}/subject", server.url()),
+            "headers": { "Metadata": "True" },
+            "format": { "type": "json", "subject_token_field_name": "value" },
+        });
+        let credentials = ExternalAccountCredentials::new(account(&server, source))
+            .with_service_account_impersonation_url(format!(
+                "{}/v1/projects/-/serviceAccounts/ci@my-project.iam.gserviceaccount.com:generateAccessToken",
+                server.url()
+            ));
+        let client = reqwest::Client::new();
+        assert_eq!(
+            credentials.token(&client).await?.as_deref(),
+            Some("impersonated")
+        );
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        let form = form(&requests[1]);
+        assert_eq!(form["subject_token"], "oidc-from-url");
+        assert_eq!(form["scope"], CLOUD_PLATFORM_SCOPE);
+        let body: serde_json::Value = serde_json::from_slice(&requests[2].body)?;
+        assert_eq!(body["scope"][0], DEVSTORAGE_SCOPE);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn exchanges_token_from_executable() -> Result<(), Box<dyn std::error::Error>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let server = sts_server();
+        let script = std::env::temp_dir().join("cloud-storage-rs-subject-token.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\n\
+             echo '{\"version\": 1, \"success\": true, \"token_type\": \"urn:ietf:params:oauth:token-type:jwt\", \"id_token\": \"oidc-from-'$GOOGLE_EXTERNAL_ACCOUNT_INTERACTIVE'\"}'\n",
+        )?;
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))?;
+        let source = serde_json::json!({ "executable": { "command": script } });
+
+        let mut credentials = ExternalAccountCredentials::new(account(&server, source));
+        credentials.allow_executables = false;
+        let client = reqwest::Client::new();
+        assert!(matches!(
+            credentials.token(&client).await,
+            Err(crate::Error::Credentials(_))
+        ));
+
+        credentials.allow_executables = true;
+        let token = credentials.token(&client).await;
+        std::fs::remove_file(&script)?;
+        assert_eq!(token?.as_deref(), Some("federated"));
+        assert_eq!(form(&server.requests()[0])["subject_token"], "oidc-from-0");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn executable_with_large_output() -> Result<(), Box<dyn std::error::Error>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        // the response is padded far beyond the size of a pipe buffer
+        let server = sts_server();
+        let script = std::env::temp_dir().join("cloud-storage-rs-large-subject-token.sh");
+        std::fs::write(
+            &script,
+            "#!/bin/sh\n\
+             printf '{\"version\": 1, \"success\": true, \"token_type\": \"urn:ietf:params:oauth:token-type:jwt\", \"id_token\": \"oidc-large\"'\n\
+             head -c 1048576 /dev/zero | tr '\\0' ' '\n\
+             echo '}'\n",
+        )?;
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755))?;
+        let source = serde_json::json!({
+            "executable": { "command": script, "timeout_millis": 5000 }
+        });
+
+        let mut credentials = ExternalAccountCredentials::new(account(&server, source));
+        credentials.allow_executables = true;
+        let token = credentials.token(&reqwest::Client::new()).await;
+        std::fs::remove_file(&script)?;
+        assert_eq!(token?.as_deref(), Some("federated"));
+        assert_eq!(form(&server.requests()[0])["subject_token"], "oidc-large");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unsuccessful_executable_response() {
+        let credentials =
+            ExternalAccountCredentials::new(account(&sts_server(), serde_json::json!({})));
+        let response = serde_json::from_value(serde_json::json!({
+            "version": 1,
+            "success": false,
+            "code": "401",
+            "message": "not logged in",
+        }))
+        .unwrap();
+        let error = credentials.executable_token(response).unwrap_err();
+        assert!(error.to_string().contains("not logged in"), "{}", error);
+    }
+}
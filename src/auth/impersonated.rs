@@ -0,0 +1,287 @@
+use super::{TokenProvider, DEVSTORAGE_SCOPE};
+use crate::token::Token;
+use futures::future::BoxFuture;
+
+/// A token provider that uses the tokens of other credentials to obtain tokens for a target
+/// service account, using the
+/// [`generateAccessToken`](https://cloud.google.com/iam/docs/reference/credentials/rest/v1/projects.serviceAccounts/generateAccessToken)
+/// method of the IAM Credentials API. This requires no key of the target service account, only
+/// the `Service Account Token Creator` role on it.
+///
+/// The source credentials must request tokens with the `iam` or `cloud-platform` scope, as the
+/// credentials of `gcloud auth application-default login` and most Compute Engine instances do.
+/// Service account keys request a Cloud Storage scope by default, so use
+/// `ImpersonatedCredentials::from_service_account` for those.
+/// ### Example
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use cloud_storage::{auth::{AuthorizedUserCredentials, ImpersonatedCredentials}, Client};
+///
+/// let credentials = ImpersonatedCredentials::new(
+///     AuthorizedUserCredentials::from_well_known_file()?,
+///     "reader@my-project.iam.gserviceaccount.com",
+/// );
+/// let client = Client::with_token_provider(credentials);
+/// let buckets = client.bucket().list().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ImpersonatedCredentials {
+    source: Box<dyn TokenProvider>,
+    target: String,
+    delegates: Vec<String>,
+    endpoint: String,
+    token_cache: Token,
+}
+
+impl ImpersonatedCredentials {
+    /// Creates a token provider that uses the tokens of `source` to impersonate the service
+    /// account with the email address `target`.
+    pub fn new(source: impl TokenProvider + 'static, target: impl Into<String>) -> Self {
+        Self {
+            source: Box::new(source),
+            target: target.into(),
+            delegates: Vec::new(),
+            endpoint: "https://iamcredentials.googleapis.com".to_string(),
+            token_cache: Token::new(DEVSTORAGE_SCOPE),
+        }
+    }
+
+    /// Creates a token provider that uses the key of `service_account` to impersonate the service
+    /// account with the email address `target`.
+    pub fn from_service_account(
+        service_account: crate::service_account::ServiceAccount,
+        target: impl Into<String>,
+    ) -> Self {
+        let source =
+            super::ServiceAccountCredentials::new(service_account).with_scope(super::IAM_SCOPE);
+        Self::new(source, target)
+    }
+
+    /// Impersonates the target through a chain of service accounts, given by their email
+    /// addresses. The source credentials must be allowed to create tokens for the first delegate,
+    /// each delegate for the next one, and the last delegate for the target.
+    pub fn with_delegates<S: Into<String>>(
+        mut self,
+        delegates: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.delegates = delegates
+            .into_iter()
+            .map(|email| format!("projects/-/serviceAccounts/{}", email.into()))
+            .collect();
+        self
+    }
+
+    /// Replaces the root of the IAM Credentials API. Defaults to
+    /// `https://iamcredentials.googleapis.com`.
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Impersonates the target again `margin` before the current token expires. Defaults to five
+    /// minutes.
+    pub fn with_refresh_margin(mut self, margin: std::time::Duration) -> Self {
+        self.token_cache = self.token_cache.with_refresh_margin(margin);
+        self
+    }
+
+    // Used for `impersonated_service_account` credentials files, which contain the full url.
+    pub(crate) fn with_url(mut self, url: &str) -> crate::Result<Self> {
+        let (endpoint, target) = url
+            .strip_suffix(":generateAccessToken")
+            .and_then(|url| url.split_once("/v1/projects/-/serviceAccounts/"))
+            .ok_or_else(|| {
+                crate::Error::Credentials(format!("invalid impersonation url `{}`", url))
+            })?;
+        self.endpoint = endpoint.to_string();
+        self.target = target.to_string();
+        Ok(self)
+    }
+
+    async fn refresh(&self, client: &reqwest::Client, scope: &str) -> crate::Result<(String, u64)> {
+        let source_token = self.source.token(client).await?.ok_or_else(|| {
+            crate::Error::Credentials(
+                "impersonation requires authenticated credentials".to_string(),
+            )
+        })?;
+        let url = format!(
+            "{}/v1/projects/-/serviceAccounts/{}:generateAccessToken",
+            self.endpoint, self.target
+        );
+        generate_access_token(client, &url, &source_token, &self.delegates, scope).await
+    }
+}
+
+impl TokenProvider for ImpersonatedCredentials {
+    fn token<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move {
+            let token = self
+                .token_cache
+                .get_or_refresh(|scope| async move { self.refresh(client, &scope).await })
+                .await?;
+            Ok(Some(token))
+        })
+    }
+
+    /// The project of the target service account.
+    fn project_id(&self) -> Option<&str> {
+        self.target
+            .split('@')
+            .nth(1)
+            .and_then(|domain| domain.strip_suffix(".iam.gserviceaccount.com"))
+            .or_else(|| self.source.project_id())
+    }
+
+    /// The email address of the target service account, which signs urls with `signBlob`.
+    fn service_account_email<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move { Ok(Some(self.target.clone())) })
+    }
+}
+
+/// The request body of the IAM Credentials `generateAccessToken` method.
+#[derive(serde::Serialize)]
+struct GenerateAccessTokenRequest<'a> {
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    delegates: &'a [String],
+    scope: [&'a str; 1],
+    lifetime: &'a str,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerateAccessTokenResponse {
+    access_token: String,
+    expire_time: String,
+}
+
+/// Exchanges `token` for an access token of the service account named in `url`, which points to
+/// the `generateAccessToken` method of the IAM Credentials API. `delegates` lists the service
+/// accounts in between, each of which may create tokens for the next.
+pub(crate) async fn generate_access_token(
+    client: &reqwest::Client,
+    url: &str,
+    token: &str,
+    delegates: &[String],
+    scope: &str,
+) -> crate::Result<(String, u64)> {
+    let body = GenerateAccessTokenRequest {
+        delegates,
+        scope: [scope],
+        lifetime: "3600s",
+    };
+    let response = client
+        .post(url)
+        .bearer_auth(token)
+        .json(&body)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await?;
+        return Err(crate::Error::Other(format!(
+            "impersonation failed with {}: {}",
+            status, body
+        )));
+    }
+    let response: GenerateAccessTokenResponse = response.json().await?;
+    let expires = chrono::DateTime::parse_from_rfc3339(&response.expire_time)
+        .map_err(|e| crate::Error::Other(format!("invalid expireTime: {}", e)))?;
+    Ok((response.access_token, expires.timestamp() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::StaticToken;
+    use crate::test_server::{Response, TestServer};
+
+    #[tokio::test]
+    async fn impersonates_through_delegates() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            let target = "/v1/projects/-/serviceAccounts/reader@my-project.iam.gserviceaccount.com:generateAccessToken";
+            if request.path == target && request.header("Authorization") == Some("Bearer source") {
+                Response::new(200).json(serde_json::json!({
+                    "accessToken": "impersonated",
+                    "expireTime": "2100-01-01T00:00:00Z",
+                }))
+            } else {
+                Response::google_error(403, "forbidden", "permission denied")
+            }
+        });
+        let credentials = ImpersonatedCredentials::new(
+            StaticToken::new("source"),
+            "reader@my-project.iam.gserviceaccount.com",
+        )
+        .with_delegates(vec!["hop@other-project.iam.gserviceaccount.com"])
+        .with_endpoint(server.url());
+        assert_eq!(credentials.project_id(), Some("my-project"));
+
+        let client = reqwest::Client::new();
+        assert_eq!(
+            credentials.token(&client).await?.as_deref(),
+            Some("impersonated")
+        );
+        // the impersonated token is cached
+        credentials.token(&client).await?;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let body: serde_json::Value = serde_json::from_slice(&requests[0].body)?;
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "delegates": ["projects/-/serviceAccounts/hop@other-project.iam.gserviceaccount.com"],
+                "scope": [DEVSTORAGE_SCOPE],
+                "lifetime": "3600s",
+            })
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn denied_impersonation() {
+        let server = TestServer::start(|_| {
+            Response::google_error(
+                403,
+                "forbidden",
+                "iam.serviceAccounts.getAccessToken denied",
+            )
+        });
+        let credentials = ImpersonatedCredentials::new(StaticToken::new("source"), "other@x.com")
+            .with_endpoint(server.url());
+        let error = credentials
+            .token(&reqwest::Client::new())
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("getAccessToken denied"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn with_url() {
+        let credentials = ImpersonatedCredentials::new(super::super::Anonymous, "")
+            .with_url(
+                "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/a@b.iam.gserviceaccount.com:generateAccessToken",
+            )
+            .unwrap();
+        assert_eq!(
+            credentials.endpoint,
+            "https://iamcredentials.googleapis.com"
+        );
+        assert_eq!(credentials.target, "a@b.iam.gserviceaccount.com");
+        assert!(ImpersonatedCredentials::new(super::super::Anonymous, "")
+            .with_url("https://example.com")
+            .is_err());
+    }
+}
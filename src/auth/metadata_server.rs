@@ -0,0 +1,164 @@
+use super::{TokenProvider, DEVSTORAGE_SCOPE};
+use crate::token::{Token, TokenResponse};
+use futures::future::BoxFuture;
+
+/// A token provider that requests access tokens for the service account attached to the Google
+/// Compute Engine instance, Cloud Run service or Google Kubernetes Engine workload this process
+/// runs on, from the [metadata server](https://cloud.google.com/compute/docs/metadata/overview).
+pub struct MetadataServer {
+    base_url: String,
+    account: String,
+    token_cache: Token,
+    email: std::sync::Mutex<Option<String>>,
+}
+
+impl Default for MetadataServer {
+    fn default() -> Self {
+        let host = std::env::var("GCE_METADATA_HOST")
+            .unwrap_or_else(|_| "metadata.google.internal".to_string());
+        Self {
+            base_url: String::new(),
+            account: "default".to_string(),
+            token_cache: Token::new(DEVSTORAGE_SCOPE),
+            email: std::sync::Mutex::new(None),
+        }
+        .with_host(&host)
+    }
+}
+
+impl MetadataServer {
+    /// Creates a token provider for the default service account, using the metadata server at
+    /// `metadata.google.internal`, or at the host given by the `GCE_METADATA_HOST` environment
+    /// variable. Equivalent to `MetadataServer::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uses the metadata server listening on `host`. When no scheme is given, `http` is used.
+    pub fn with_host(mut self, host: &str) -> Self {
+        let host = host.trim_end_matches('/');
+        self.base_url = if host.starts_with("http://") || host.starts_with("https://") {
+            host.to_string()
+        } else {
+            format!("http://{}", host)
+        };
+        self
+    }
+
+    /// Requests tokens for the service account with this email address, instead of for the
+    /// default service account of the instance.
+    pub fn with_service_account(mut self, email: &str) -> Self {
+        self.account = email.to_string();
+        self
+    }
+
+    /// Requests a new token `margin` before the current one expires. Defaults to five minutes.
+    pub fn with_refresh_margin(mut self, margin: std::time::Duration) -> Self {
+        self.token_cache = self.token_cache.with_refresh_margin(margin);
+        self
+    }
+
+    async fn refresh(&self, client: &reqwest::Client) -> crate::Result<(String, u64)> {
+        let url = format!(
+            "{}/computeMetadata/v1/instance/service-accounts/{}/token",
+            self.base_url, self.account
+        );
+        let response = client
+            .get(&url)
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .await?;
+        Ok(TokenResponse::parse(response).await?.into_token())
+    }
+
+    async fn fetch_email(&self, client: &reqwest::Client) -> crate::Result<String> {
+        let url = format!(
+            "{}/computeMetadata/v1/instance/service-accounts/{}/email",
+            self.base_url, self.account
+        );
+        let response = client
+            .get(&url)
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(crate::Error::Other(format!(
+                "cannot read the service account from the metadata server, {}: {}",
+                status, body
+            )));
+        }
+        Ok(body.trim().to_string())
+    }
+}
+
+impl TokenProvider for MetadataServer {
+    fn token<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move {
+            let token = self
+                .token_cache
+                .get_or_refresh(|_| self.refresh(client))
+                .await?;
+            Ok(Some(token))
+        })
+    }
+
+    /// The email address of the service account, which is read from the metadata server once.
+    fn service_account_email<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move {
+            if self.account != "default" {
+                return Ok(Some(self.account.clone()));
+            }
+            if let Some(email) = self.email.lock().unwrap().clone() {
+                return Ok(Some(email));
+            }
+            let email = self.fetch_email(client).await?;
+            *self.email.lock().unwrap() = Some(email.clone());
+            Ok(Some(email))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Response, TestServer};
+
+    #[tokio::test]
+    async fn requests_token_from_metadata_server() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            if request.path == "/computeMetadata/v1/instance/service-accounts/default/token"
+                && request.header("Metadata-Flavor") == Some("Google")
+            {
+                Response::new(200).json(serde_json::json!({
+                    "access_token": "ya29.instance",
+                    "expires_in": 3599,
+                    "token_type": "Bearer",
+                }))
+            } else {
+                Response::new(404).body("not found")
+            }
+        });
+        let metadata = MetadataServer::new().with_host(server.url());
+        let client = reqwest::Client::new();
+        assert_eq!(
+            metadata.token(&client).await?.as_deref(),
+            Some("ya29.instance")
+        );
+        metadata.token(&client).await?;
+        assert_eq!(server.requests().len(), 1);
+
+        let other = MetadataServer::new()
+            .with_host(server.url())
+            .with_service_account("robot@my-project.iam.gserviceaccount.com");
+        assert!(other.token(&client).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use super::{TokenProvider, DEVSTORAGE_SCOPE};
+use crate::{resources::service_account::ServiceAccount, token::Token};
+use futures::future::BoxFuture;
+
+/// A token provider that signs a JWT with the private key of a `ServiceAccount` and exchanges it
+/// for an access token.
+pub struct ServiceAccountCredentials {
+    service_account: ServiceAccount,
+    token_url: String,
+    token_cache: Token,
+}
+
+impl ServiceAccountCredentials {
+    /// Creates a token provider that authenticates as `service_account`.
+    pub fn new(service_account: ServiceAccount) -> Self {
+        Self {
+            service_account,
+            token_url: "https://www.googleapis.com/oauth2/v4/token".to_string(),
+            token_cache: Token::new(DEVSTORAGE_SCOPE),
+        }
+    }
+
+    /// Replaces the url where the signed JWT is exchanged for an access token. Defaults to
+    /// `https://www.googleapis.com/oauth2/v4/token`.
+    pub fn with_token_url(mut self, token_url: impl Into<String>) -> Self {
+        self.token_url = token_url.into();
+        self
+    }
+
+    /// Requests tokens with `scope` instead of full control over Cloud Storage, for example
+    /// `auth::IAM_SCOPE` to impersonate other service accounts.
+    pub fn with_scope(mut self, scope: &str) -> Self {
+        self.token_cache = self.token_cache.with_scope(scope);
+        self
+    }
+
+    /// Refreshes the access token `margin` before it expires. Defaults to five minutes.
+    pub fn with_refresh_margin(mut self, margin: std::time::Duration) -> Self {
+        self.token_cache = self.token_cache.with_refresh_margin(margin);
+        self
+    }
+
+    /// The service account these credentials authenticate as.
+    pub fn service_account(&self) -> &ServiceAccount {
+        &self.service_account
+    }
+}
+
+impl TokenProvider for ServiceAccountCredentials {
+    fn token<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move {
+            let token = self
+                .token_cache
+                .get(client, &self.service_account, &self.token_url)
+                .await?;
+            Ok(Some(token))
+        })
+    }
+
+    fn project_id(&self) -> Option<&str> {
+        Some(&self.service_account.project_id)
+    }
+
+    fn service_account_email<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+    ) -> BoxFuture<'a, crate::Result<Option<String>>> {
+        Box::pin(async move { Ok(Some(self.service_account.client_email.clone())) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{Response, TestServer};
+
+    #[tokio::test]
+    async fn exchanges_signed_jwt() -> Result<(), Box<dyn std::error::Error>> {
+        let (service_account, key) = crate::test_server::service_account();
+
+        let server = TestServer::start(|_| {
+            Response::new(200).json(serde_json::json!({
+                "access_token": "ya29.token",
+                "expires_in": 3599,
+                "token_type": "Bearer",
+            }))
+        });
+        let token_url = format!("{}/token", server.url());
+        let credentials =
+            ServiceAccountCredentials::new(service_account).with_token_url(&token_url);
+        let client = reqwest::Client::new();
+        assert_eq!(
+            credentials.token(&client).await?.as_deref(),
+            Some("ya29.token")
+        );
+        // the token is cached
+        credentials.token(&client).await?;
+        assert_eq!(credentials.project_id(), Some("my-project"));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        let body = String::from_utf8(requests[0].body.clone())?;
+        assert!(body.starts_with(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Ajwt-bearer&assertion="
+        ));
+        let jwt = body.rsplit('=').next().unwrap();
+        let mut validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::RS256);
+        validation.set_audience(&[&token_url]);
+        let public_key_pem = key.public_key_to_pem()?;
+        let public_key = jsonwebtoken::DecodingKey::from_rsa_pem(&public_key_pem)?;
+        let claims = jsonwebtoken::decode::<serde_json::Value>(jwt, &public_key, &validation)?;
+        assert_eq!(
+            claims.claims["iss"],
+            "robot@my-project.iam.gserviceaccount.com"
+        );
+        assert_eq!(claims.claims["scope"], DEVSTORAGE_SCOPE);
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use std::ops::{Range, RangeFrom};
+
+/// A range of bytes within an object, used to download only part of it. A `ByteRange` can be
+/// created from a `Range<u64>` such as `10..20` or a `RangeFrom<u64>` such as `10..`.
+///
+/// ### Example
+/// ```rust
+/// use cloud_storage::ByteRange;
+///
+/// let first_kilobyte: ByteRange = (0..1024).into();
+/// let skip_header: ByteRange = (512..).into();
+/// let parquet_footer = ByteRange::Suffix(8);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ByteRange {
+    /// The bytes from `start` up to, but not including, `end`.
+    Bounded(Range<u64>),
+    /// All bytes starting at this offset.
+    Offset(u64),
+    /// The last this many bytes of the object.
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// The value of the HTTP `Range` header that requests this range.
+    pub(crate) fn header_value(&self) -> crate::Result<String> {
+        match self {
+            Self::Bounded(range) if range.start >= range.end => Err(crate::Error::Other(format!(
+                "byte range {}..{} is empty",
+                range.start, range.end
+            ))),
+            Self::Bounded(range) => Ok(format!("bytes={}-{}", range.start, range.end - 1)),
+            Self::Offset(start) => Ok(format!("bytes={}-", start)),
+            Self::Suffix(0) => Err(crate::Error::new("byte range suffix may not be 0")),
+            Self::Suffix(length) => Ok(format!("bytes=-{}", length)),
+        }
+    }
+}
+
+impl From<Range<u64>> for ByteRange {
+    fn from(range: Range<u64>) -> Self {
+        Self::Bounded(range)
+    }
+}
+
+impl From<RangeFrom<u64>> for ByteRange {
+    fn from(range: RangeFrom<u64>) -> Self {
+        Self::Offset(range.start)
+    }
+}
+
+/// The part of an object that was returned by a ranged download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    /// The offset of the first returned byte.
+    pub start: u64,
+    /// The offset one past the last returned byte.
+    pub end: u64,
+    /// The size of the whole object, if Google reported it.
+    pub total_size: Option<u64>,
+}
+
+impl ContentRange {
+    /// Parses the value of a `Content-Range` header, such as `bytes 0-99/1234`.
+    pub(crate) fn parse(header: &str) -> Option<Self> {
+        let range = header.trim().strip_prefix("bytes ")?;
+        let (span, total) = range.split_at(range.find('/')?);
+        let (start, end) = span.split_at(span.find('-')?);
+        let total_size = match &total[1..] {
+            "*" => None,
+            total => Some(total.parse().ok()?),
+        };
+        Some(Self {
+            start: start.parse().ok()?,
+            end: end[1..].parse::<u64>().ok()? + 1,
+            total_size,
+        })
+    }
+
+    /// The range of a response that contains the whole object of `size` bytes.
+    pub(crate) fn full(size: u64) -> Self {
+        Self {
+            start: 0,
+            end: size,
+            total_size: Some(size),
+        }
+    }
+
+    /// The number of returned bytes.
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Whether no bytes were returned.
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_values() -> Result<(), Box<dyn std::error::Error>> {
+        assert_eq!(ByteRange::from(10..20).header_value()?, "bytes=10-19");
+        assert_eq!(ByteRange::from(10..).header_value()?, "bytes=10-");
+        assert_eq!(ByteRange::Suffix(8).header_value()?, "bytes=-8");
+        assert!(ByteRange::from(10..10).header_value().is_err());
+        assert!(ByteRange::Suffix(0).header_value().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_content_range() {
+        assert_eq!(
+            ContentRange::parse("bytes 10-19/1234"),
+            Some(ContentRange {
+                start: 10,
+                end: 20,
+                total_size: Some(1234)
+            })
+        );
+        assert_eq!(
+            ContentRange::parse("bytes 0-0/*"),
+            Some(ContentRange {
+                start: 0,
+                end: 1,
+                total_size: None
+            })
+        );
+        assert_eq!(ContentRange::parse("bytes */1234"), None);
+        assert_eq!(ContentRange::parse("items 0-1/2"), None);
+    }
+}
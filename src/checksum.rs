@@ -0,0 +1,306 @@
+use crate::Error;
+use futures::Stream;
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Which checksums are computed over the data of uploads and downloads, to detect corruption in
+/// transit. Uploads send the checksums to Google, which rejects the upload if they do not match,
+/// and downloads compare them against the `x-goog-hash` header of the response. A mismatch is
+/// reported as `Error::ChecksumMismatch`.
+///
+/// Composite objects do not have an MD5 hash, in which case only the CRC32C checksum is verified.
+/// Ranged downloads and downloads of objects that are decompressed while being served are never
+/// verified.
+/// ### Example
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use cloud_storage::{Checksum, Client};
+///
+/// let client = Client::default();
+/// let bytes = client
+///     .object()
+///     .with_checksum(Checksum::None)
+///     .download("my_bucket", "path/to/my/file.png")
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checksum {
+    /// Do not compute or verify checksums.
+    None,
+    /// Compute and verify the CRC32C checksum. This is the default.
+    Crc32c,
+    /// Compute and verify both the CRC32C checksum and the MD5 hash. Requests fail with
+    /// `Error::Ssl` if OpenSSL does not provide MD5, e.g. because it runs in FIPS mode.
+    Crc32cAndMd5,
+}
+
+// written out, because `#[default]` on a variant requires Rust 1.62
+#[allow(clippy::derivable_impls)]
+impl Default for Checksum {
+    fn default() -> Self {
+        Self::Crc32c
+    }
+}
+
+impl Checksum {
+    /// A hasher that computes the checksums selected by `self`, or `None` if checksums are
+    /// disabled. Fails if the MD5 hash was requested but OpenSSL does not provide it, as is the
+    /// case in FIPS mode, rather than silently verifying only the CRC32C checksum.
+    pub(crate) fn hasher(self) -> crate::Result<Option<Hasher>> {
+        Ok(match self {
+            Self::None => None,
+            Self::Crc32c => Some(Hasher {
+                crc32c: 0,
+                md5: None,
+            }),
+            Self::Crc32cAndMd5 => Some(Hasher {
+                crc32c: 0,
+                md5: Some(openssl::hash::Hasher::new(
+                    openssl::hash::MessageDigest::md5(),
+                )?),
+            }),
+        })
+    }
+}
+
+/// Incrementally computes the checksums of data as it is uploaded or downloaded.
+pub(crate) struct Hasher {
+    crc32c: u32,
+    md5: Option<openssl::hash::Hasher>,
+}
+
+impl Hasher {
+    pub fn update(&mut self, data: &[u8]) {
+        self.crc32c = crc32c::crc32c_append(self.crc32c, data);
+        if let Some(md5) = self.md5.as_mut() {
+            // updating only fails if the hasher was already finished
+            let _ = md5.update(data);
+        }
+    }
+
+    pub fn finish(mut self) -> crate::Result<Hashes> {
+        let md5 = match self.md5.as_mut() {
+            Some(md5) => Some(base64::encode(md5.finish()?)),
+            None => None,
+        };
+        Ok(Hashes {
+            crc32c: Some(base64::encode(self.crc32c.to_be_bytes())),
+            md5,
+        })
+    }
+}
+
+/// Base64 encoded checksums, in the format used by the `x-goog-hash` header and the `crc32c` and
+/// `md5Hash` fields of objects.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct Hashes {
+    pub crc32c: Option<String>,
+    pub md5: Option<String>,
+}
+
+impl Hashes {
+    /// Computes the checksums selected by `checksum` over `data`.
+    pub fn compute(checksum: Checksum, data: &[u8]) -> crate::Result<Option<Self>> {
+        match checksum.hasher()? {
+            Some(mut hasher) => {
+                hasher.update(data);
+                Ok(Some(hasher.finish()?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Parses the `x-goog-hash` headers of a response, which look like
+    /// `crc32c=n03x6A==,md5=Ojk9c3dhfxgoKVVHYwFbHQ==`. Google may send either one header with
+    /// both hashes or one header per hash.
+    pub fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let mut hashes = Self::default();
+        let values = headers
+            .get_all("x-goog-hash")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','));
+        for value in values {
+            let value = value.trim();
+            if let Some(crc32c) = value.strip_prefix("crc32c=") {
+                hashes.crc32c = Some(crc32c.to_string());
+            } else if let Some(md5) = value.strip_prefix("md5=") {
+                hashes.md5 = Some(md5.to_string());
+            }
+        }
+        hashes
+    }
+
+    /// The checksums of an uploaded object, as reported in its `crc32c` and `md5Hash` fields.
+    pub fn from_object(object: &crate::Object) -> Self {
+        Self {
+            crc32c: Some(object.crc32c.clone()),
+            md5: object.md5_hash.clone(),
+        }
+    }
+
+    /// The value of the `x-goog-hash` header that sends these checksums with an upload.
+    pub fn header_value(&self) -> String {
+        let crc32c = self.crc32c.iter().map(|c| format!("crc32c={}", c));
+        let md5 = self.md5.iter().map(|m| format!("md5={}", m));
+        crc32c.chain(md5).collect::<Vec<_>>().join(",")
+    }
+
+    /// Compares the checksums computed locally in `self` with the checksums reported by Google.
+    /// Checksums that are missing on either side are not compared.
+    pub fn verify(&self, reported: &Hashes) -> crate::Result<()> {
+        let pairs = [
+            ("crc32c", &self.crc32c, &reported.crc32c),
+            ("md5", &self.md5, &reported.md5),
+        ];
+        for (algorithm, actual, expected) in pairs.iter() {
+            if let (Some(actual), Some(expected)) = (actual, expected) {
+                if actual != expected {
+                    return Err(Error::ChecksumMismatch {
+                        algorithm,
+                        expected: expected.clone(),
+                        actual: actual.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wraps the body of a download, hashing every chunk and yielding an `Error::ChecksumMismatch` at
+/// the end of the stream if the checksums do not match the reported ones.
+pub(crate) struct VerifyingStream<S> {
+    inner: S,
+    hasher: Option<Hasher>,
+    reported: Hashes,
+    done: bool,
+}
+
+impl<S> VerifyingStream<S> {
+    pub fn new(inner: S, hasher: Option<Hasher>, reported: Hashes) -> Self {
+        Self {
+            inner,
+            hasher,
+            reported,
+            done: false,
+        }
+    }
+}
+
+impl<S, E> Stream for VerifyingStream<S>
+where
+    S: Stream<Item = Result<bytes::Bytes, E>> + Unpin,
+    Error: From<E>,
+{
+    type Item = crate::Result<bytes::Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        match Pin::new(&mut self.inner).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                if let Some(hasher) = self.hasher.as_mut() {
+                    hasher.update(&chunk);
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            Poll::Ready(Some(Err(e))) => {
+                self.hasher = None;
+                Poll::Ready(Some(Err(e.into())))
+            }
+            Poll::Ready(None) => {
+                self.done = true;
+                let result = match self.hasher.take() {
+                    Some(hasher) => hasher
+                        .finish()
+                        .and_then(|hashes| hashes.verify(&self.reported)),
+                    None => Ok(()),
+                };
+                match result {
+                    Ok(()) => Poll::Ready(None),
+                    Err(e) => Poll::Ready(Some(Err(e))),
+                }
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_hashes() -> Result<(), Box<dyn std::error::Error>> {
+        // the checksums Google reports for an object containing `hello world`
+        let hashes = Hashes::compute(Checksum::Crc32cAndMd5, b"hello world")?.unwrap();
+        assert_eq!(hashes.crc32c.as_deref(), Some("yZRlqg=="));
+        assert_eq!(hashes.md5.as_deref(), Some("XrY7u+Ae7tCTyyK7j1rNww=="));
+        assert_eq!(
+            hashes.header_value(),
+            "crc32c=yZRlqg==,md5=XrY7u+Ae7tCTyyK7j1rNww=="
+        );
+
+        // MD5 is either computed or reported as an error, never silently skipped
+        assert!(Checksum::Crc32cAndMd5.hasher()?.unwrap().md5.is_some());
+
+        let hashes = Hashes::compute(Checksum::Crc32c, b"hello world")?.unwrap();
+        assert_eq!(hashes.md5, None);
+        assert!(Hashes::compute(Checksum::None, b"hello world")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_headers() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.append("x-goog-hash", "crc32c=yZRlqg==".parse().unwrap());
+        headers.append(
+            "x-goog-hash",
+            "md5=XrY7u+Ae7tCTyyK7j1rNww==".parse().unwrap(),
+        );
+        let hashes = Hashes::from_headers(&headers);
+        assert_eq!(hashes.crc32c.as_deref(), Some("yZRlqg=="));
+        assert_eq!(hashes.md5.as_deref(), Some("XrY7u+Ae7tCTyyK7j1rNww=="));
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert(
+            "x-goog-hash",
+            "crc32c=yZRlqg==, md5=XrY7u+Ae7tCTyyK7j1rNww=="
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(Hashes::from_headers(&headers), hashes);
+    }
+
+    #[test]
+    fn verify() {
+        let actual = Hashes {
+            crc32c: Some("yZRlqg==".to_string()),
+            md5: None,
+        };
+        let reported = Hashes {
+            crc32c: Some("AAAAAA==".to_string()),
+            md5: Some("XrY7u+Ae7tCTyyK7j1rNww==".to_string()),
+        };
+        match actual.verify(&reported) {
+            Err(Error::ChecksumMismatch {
+                algorithm,
+                expected,
+                actual,
+            }) => {
+                assert_eq!(algorithm, "crc32c");
+                assert_eq!(expected, "AAAAAA==");
+                assert_eq!(actual, "yZRlqg==");
+            }
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+        assert!(actual.verify(&Hashes::default()).is_ok());
+    }
+}
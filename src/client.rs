@@ -0,0 +1,617 @@
+use crate::{
+    auth::{
+        Anonymous, AuthorizedUser, AuthorizedUserCredentials, ExternalAccount,
+        ExternalAccountCredentials, ImpersonatedCredentials, MetadataServer,
+        ServiceAccountCredentials, TokenProvider, Unavailable,
+    },
+    error::GoogleResponse,
+    resources::{common::ListResponse, service_account::ServiceAccount},
+    RetryPolicy, UrlSigner,
+};
+use futures::{stream, Stream};
+
+mod endpoints;
+
+mod bucket;
+mod bucket_access_control;
+mod default_object_access_control;
+mod hmac_key;
+mod object;
+mod object_access_control;
+mod object_stream;
+mod resumable_upload;
+
+pub use bucket::BucketClient;
+pub use bucket_access_control::BucketAccessControlClient;
+pub use default_object_access_control::DefaultObjectAccessControlClient;
+pub use endpoints::Endpoints;
+pub use hmac_key::HmacKeyClient;
+pub use object::ObjectClient;
+pub use object_access_control::ObjectAccessControlClient;
+pub use object_stream::ObjectStream;
+pub use resumable_upload::{
+    ResumableUpload, UploadStatus, CHUNK_SIZE_MULTIPLE, DEFAULT_CHUNK_SIZE,
+};
+
+/// The primary entrypoint to perform operations with Google Cloud Storage. Every `Client` holds
+/// its own connection pool and `TokenProvider`, so a single process can talk to several projects
+/// using different credentials.
+///
+/// The free functions such as `Object::create` and `Bucket::read` use a default `Client`, which
+/// finds its credentials in the following places:
+/// 1. When the `STORAGE_EMULATOR_HOST` environment variable is set, the default `Client` talks to
+///    the emulator at that address and does not authenticate.
+/// 2. The credentials file named by the `SERVICE_ACCOUNT` or `GOOGLE_APPLICATION_CREDENTIALS`
+///    environment variable, which contains a service account key, `authorized_user` credentials,
+///    `impersonated_service_account` credentials or an `external_account` configuration for
+///    workload identity federation.
+/// 3. The credentials stored by `gcloud auth application-default login`.
+/// 4. The metadata server of the Google Compute Engine instance the process runs on.
+/// ### Example
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use cloud_storage::Client;
+///
+/// let client = Client::default();
+/// let buckets = client.bucket().list().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Client {
+    pub(crate) client: reqwest::Client,
+    pub(crate) service_account: Option<ServiceAccount>,
+    pub(crate) project_id: Option<String>,
+    pub(crate) endpoints: Endpoints,
+    pub(crate) retry_policy: RetryPolicy,
+    token_provider: std::sync::Arc<dyn TokenProvider>,
+}
+
+impl Default for Client {
+    /// Constructs a client with the default credentials. When they cannot be loaded, every
+    /// request made by the client fails with `Error::Credentials`.
+    fn default() -> Self {
+        Self::from_env().unwrap_or_else(|e| Self::with_token_provider(Unavailable(e.to_string())))
+    }
+}
+
+impl Client {
+    /// Constructs a client with the default credentials, see the documentation of `Client` for
+    /// where they are found. Equivalent to `Client::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Constructs a client with the default credentials, see the documentation of `Client` for
+    /// where they are found. Unlike `Client::default()`, this returns an error when the
+    /// credentials file cannot be read or is malformed, so that this can be detected at startup.
+    /// ### Example
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::from_env()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_env() -> crate::Result<Self> {
+        dotenv::dotenv().ok();
+        if let Ok(host) = std::env::var("STORAGE_EMULATOR_HOST") {
+            if !host.is_empty() {
+                return Ok(Self::emulator(&host));
+            }
+        }
+        if let Some(path) = crate::auth::credentials_path() {
+            let json = crate::auth::read_credentials(path.as_ref())?;
+            return Self::from_credentials_json(&json);
+        }
+        match AuthorizedUserCredentials::from_well_known_file() {
+            Ok(credentials) => Ok(Self::with_token_provider(credentials)),
+            Err(_) => Ok(Self::with_token_provider(MetadataServer::new())),
+        }
+    }
+
+    /// Constructs a client that authenticates using the provided `ServiceAccount`.
+    /// ### Example
+    /// ```no_run
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Client, service_account::ServiceAccount};
+    ///
+    /// let service_account = ServiceAccount::from_file("tenant-a.json")?;
+    /// let client = Client::with_service_account(service_account);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_service_account(service_account: ServiceAccount) -> Self {
+        let credentials = ServiceAccountCredentials::new(service_account.clone());
+        Self {
+            service_account: Some(service_account),
+            ..Self::with_token_provider(credentials)
+        }
+    }
+
+    /// Constructs a client that authenticates using the access tokens of `token_provider`. The
+    /// project of the client is the project of the credentials, if they specify one.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{auth::Anonymous, Client};
+    ///
+    /// let client = Client::with_token_provider(Anonymous);
+    /// let object = client.object().read("gcp-public-data-landsat", "index.csv.gz").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_token_provider(token_provider: impl TokenProvider + 'static) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            service_account: None,
+            project_id: token_provider.project_id().map(str::to_string),
+            endpoints: Endpoints::default(),
+            retry_policy: RetryPolicy::default(),
+            token_provider: std::sync::Arc::new(token_provider),
+        }
+    }
+
+    // Constructs a client from the contents of a credentials file, which contains a service
+    // account key, `authorized_user` credentials or an `external_account` configuration.
+    fn from_credentials_json(json: &str) -> crate::Result<Self> {
+        #[derive(serde::Deserialize)]
+        struct Credentials {
+            r#type: String,
+        }
+
+        let invalid =
+            |e| crate::Error::Credentials(format!("the credentials are not valid: {}", e));
+        let credentials: Credentials = serde_json::from_str(json).map_err(invalid)?;
+        match credentials.r#type.as_str() {
+            "service_account" => Ok(Self::with_service_account(ServiceAccount::from_json_str(
+                json,
+            )?)),
+            "authorized_user" => {
+                let user: AuthorizedUser = serde_json::from_str(json).map_err(invalid)?;
+                Ok(Self::with_token_provider(AuthorizedUserCredentials::new(
+                    user,
+                )))
+            }
+            "impersonated_service_account" => {
+                #[derive(serde::Deserialize)]
+                struct Impersonated {
+                    service_account_impersonation_url: String,
+                    #[serde(default)]
+                    delegates: Vec<String>,
+                    source_credentials: serde_json::Value,
+                }
+
+                let impersonated: Impersonated = serde_json::from_str(json).map_err(invalid)?;
+                let source = impersonated.source_credentials;
+                let url = impersonated.service_account_impersonation_url;
+                let delegates = impersonated
+                    .delegates
+                    .iter()
+                    .map(|delegate| delegate.rsplit('/').next().unwrap_or(delegate).to_string());
+                let credentials = match source["type"].as_str() {
+                    Some("service_account") => {
+                        let service_account = ServiceAccount::from_json_str(&source.to_string())?;
+                        ImpersonatedCredentials::from_service_account(service_account, "")
+                    }
+                    Some("authorized_user") => {
+                        let user = serde_json::from_value(source).map_err(invalid)?;
+                        ImpersonatedCredentials::new(AuthorizedUserCredentials::new(user), "")
+                    }
+                    other => {
+                        return Err(crate::Error::Credentials(format!(
+                            "unsupported source credentials type `{}`",
+                            other.unwrap_or_default()
+                        )))
+                    }
+                };
+                Ok(Self::with_token_provider(
+                    credentials.with_url(&url)?.with_delegates(delegates),
+                ))
+            }
+            "external_account" => {
+                let account: ExternalAccount = serde_json::from_str(json).map_err(invalid)?;
+                Ok(Self::with_token_provider(ExternalAccountCredentials::new(
+                    account,
+                )))
+            }
+            other => Err(crate::Error::Credentials(format!(
+                "unsupported credentials type `{}`",
+                other
+            ))),
+        }
+    }
+
+    /// Constructs a client that talks to a Cloud Storage emulator such as
+    /// [fake-gcs-server](https://github.com/fsouza/fake-gcs-server) listening on `host`. Requests
+    /// made by this client are not authenticated. `host` may be given with or without a scheme,
+    /// `http` is assumed when it is left out.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::emulator("localhost:4443").with_project_id("test-project");
+    /// let buckets = client.bucket().list().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn emulator(host: &str) -> Self {
+        Self::with_token_provider(Anonymous).with_endpoints(Endpoints::emulator(host))
+    }
+
+    /// Replaces the endpoints this client sends its requests to.
+    /// ### Example
+    /// ```no_run
+    /// use cloud_storage::{Client, client::Endpoints};
+    ///
+    /// let client = Client::default().with_endpoints(Endpoints {
+    ///     json_api: "http://localhost:8080/storage/v1".to_string(),
+    ///     ..Endpoints::default()
+    /// });
+    /// ```
+    pub fn with_endpoints(mut self, endpoints: Endpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    /// Sets the project that is used when creating and listing `Bucket`s and `HmacKey`s. Defaults
+    /// to the project of the service account.
+    pub fn with_project_id(mut self, project_id: impl Into<String>) -> Self {
+        self.project_id = Some(project_id.into());
+        self
+    }
+
+    /// Replaces the `reqwest::Client` used to perform requests, for example to configure timeouts
+    /// or a proxy.
+    pub fn with_reqwest_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Sets the policy that determines how often and how long requests that fail with a transient
+    /// error are retried. See `RetryPolicy` for the requests that are retried.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// The service account this client uses to authenticate, or `None` if requests are not
+    /// authenticated.
+    pub fn service_account(&self) -> Option<&ServiceAccount> {
+        self.service_account.as_ref()
+    }
+
+    /// The endpoints this client sends its requests to.
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    /// Operations on `Bucket`s.
+    pub fn bucket(&self) -> BucketClient<'_> {
+        BucketClient(self)
+    }
+
+    /// Operations on `BucketAccessControl`s.
+    pub fn bucket_access_control(&self) -> BucketAccessControlClient<'_> {
+        BucketAccessControlClient(self)
+    }
+
+    /// Operations on `DefaultObjectAccessControl`s.
+    pub fn default_object_access_control(&self) -> DefaultObjectAccessControlClient<'_> {
+        DefaultObjectAccessControlClient(self)
+    }
+
+    /// Operations on `HmacKey`s.
+    pub fn hmac_key(&self) -> HmacKeyClient<'_> {
+        HmacKeyClient(self)
+    }
+
+    /// A `UrlSigner` that signs urls and POST policies for the download endpoint of this client.
+    /// It signs with the private key of the service account of this client, or otherwise with the
+    /// IAM Credentials `signBlob` method as the service account of the `TokenProvider`, such as
+    /// the service account of the metadata server.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Client, SignedUrlOptions, UrlStyle};
+    ///
+    /// let client = Client::default();
+    /// let signer = client
+    ///     .url_signer()
+    ///     .with_region("europe-west4")
+    ///     .with_url_style(UrlStyle::VirtualHostedStyle);
+    /// let url = signer.sign("my_bucket", "report.pdf", 600, &SignedUrlOptions::get()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn url_signer(&self) -> UrlSigner {
+        let signer = match self.service_account {
+            Some(ref service_account) => UrlSigner::new(service_account),
+            None => UrlSigner::sign_blob_with(self.client.clone(), self.token_provider.clone()),
+        };
+        signer.with_endpoint(&self.endpoints.download)
+    }
+
+    /// Operations on `Object`s.
+    pub fn object(&self) -> ObjectClient<'_> {
+        ObjectClient {
+            client: self,
+            checksum: crate::Checksum::default(),
+            preconditions: crate::Preconditions::default(),
+            generation: None,
+            encryption_key: None,
+            source_encryption_key: None,
+        }
+    }
+
+    /// Operations on `ObjectAccessControl`s.
+    pub fn object_access_control(&self) -> ObjectAccessControlClient<'_> {
+        ObjectAccessControlClient(self, None)
+    }
+
+    /// Sends `request`, retrying it according to the retry policy of this client if it is
+    /// `idempotent` and fails with a transient error. If the last attempt is answered with an
+    /// unsuccessful status, that response is returned so the caller can turn it into an error.
+    /// Requests with a streaming body cannot be repeated and are sent only once.
+    pub(crate) async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        idempotent: bool,
+    ) -> crate::Result<reqwest::Response> {
+        let started = std::time::Instant::now();
+        let mut failures = 0;
+        loop {
+            let attempt = match request.try_clone() {
+                Some(attempt) if idempotent => attempt,
+                _ => return Ok(request.send().await?),
+            };
+            let result = attempt.send().await.map_err(crate::Error::from);
+            let transient = match result {
+                Ok(ref response) => crate::error::is_retryable_status(response.status().as_u16()),
+                Err(ref e) => e.retryable(),
+            };
+            failures += 1;
+            let backoff = match self.retry_policy.backoff(failures, started.elapsed()) {
+                Some(backoff) if transient => backoff,
+                _ => return result,
+            };
+            tokio::time::delay_for(backoff).await;
+        }
+    }
+
+    /// Lists the pages of a collection at `url`, starting at the page of `page_token`. Every page
+    /// is only requested once the previous one has been consumed, by following its
+    /// `nextPageToken`. A page that fails is retried when the stream is polled again.
+    pub(crate) fn list_pages<T>(
+        &self,
+        url: String,
+        query: Vec<(&'static str, String)>,
+        page_token: Option<String>,
+    ) -> impl Stream<Item = crate::Result<ListResponse<T>>> + Send + '_
+    where
+        T: serde::de::DeserializeOwned + Send + 'static,
+    {
+        #[derive(Clone)]
+        enum ListState {
+            Start,
+            HasMore(String),
+            Done,
+        }
+        use ListState::*;
+
+        let start = match page_token {
+            Some(page_token) => HasMore(page_token),
+            None => Start,
+        };
+        stream::unfold(start, move |state| {
+            let url = url.clone();
+            let mut query = query.clone();
+            async move {
+                match state.clone() {
+                    HasMore(page_token) => query.push(("pageToken", page_token)),
+                    Done => return None,
+                    Start => {}
+                };
+                let headers = match self.get_headers().await {
+                    Ok(h) => h,
+                    Err(e) => return Some((Err(e), state)),
+                };
+
+                let request = self.client.get(&url).query(&query).headers(headers);
+                let response = match self.send(request, true).await {
+                    Ok(r) => r,
+                    Err(e) => return Some((Err(e), state)),
+                };
+                let result: GoogleResponse<ListResponse<T>> = match response.json().await {
+                    Ok(json) => json,
+                    Err(e) => return Some((Err(e.into()), state)),
+                };
+                let page = match result {
+                    GoogleResponse::Success(page) => page,
+                    GoogleResponse::Error(e) => return Some((Err(e.into()), state)),
+                };
+
+                let next_state = match page.next_page_token {
+                    Some(ref page_token) => HasMore(page_token.clone()),
+                    None => Done,
+                };
+                Some((Ok(page), next_state))
+            }
+        })
+    }
+
+    pub(crate) fn project_id(&self) -> crate::Result<&str> {
+        self.project_id
+            .as_deref()
+            .ok_or_else(|| crate::Error::new("no project id is configured for this client"))
+    }
+
+    pub(crate) async fn get_headers(&self) -> crate::Result<reqwest::header::HeaderMap> {
+        let mut result = reqwest::header::HeaderMap::new();
+        let token = match self.token_provider.token(&self.client).await? {
+            Some(token) => token,
+            None => return Ok(result),
+        };
+        result.insert(
+            reqwest::header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
+        );
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn list_buckets() -> Result<(), Box<dyn std::error::Error>> {
+        let client = Client::default();
+        client.bucket().list().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_and_read_object() -> Result<(), Box<dyn std::error::Error>> {
+        let bucket = crate::read_test_bucket().await;
+        let client = Client::default();
+        client
+            .object()
+            .create(&bucket.name, vec![0, 1], "test-client-create", "text/plain")
+            .await?;
+        let object = client
+            .object()
+            .read(&bucket.name, "test-client-create")
+            .await?;
+        assert_eq!(object.size, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn emulator_requests_are_not_authenticated() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::test_server::{object_json, Response, TestServer};
+
+        let server = TestServer::start(|request| match request.method.as_str() {
+            "GET" => Response::new(200).json(serde_json::json!({ "kind": "storage#buckets" })),
+            _ => Response::new(200).json(object_json("bucket", "file", &[0, 1])),
+        });
+        let client = server.client();
+        assert!(client.bucket().list().await?.is_empty());
+        let object = client
+            .object()
+            .create("bucket", vec![0, 1], "file", "text/plain")
+            .await?;
+        assert_eq!(object.size, 2);
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/storage/v1/b/?project=test-project");
+        assert_eq!(
+            requests[1].path,
+            "/upload/storage/v1/b/bucket/o?uploadType=media&name=file"
+        );
+        assert_eq!(requests[1].body, vec![0, 1]);
+        assert!(requests.iter().all(|r| r.header("Authorization").is_none()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unusable_credentials_fail_requests() {
+        assert!(matches!(
+            Client::from_credentials_json("{\"type\": \"external_thing\"}"),
+            Err(crate::Error::Credentials(_))
+        ));
+        assert!(matches!(
+            Client::from_credentials_json("not json"),
+            Err(crate::Error::Credentials(_))
+        ));
+
+        let client = Client::with_token_provider(Unavailable("no credentials".to_string()))
+            .with_project_id("test-project");
+        let error = client.bucket().list().await.unwrap_err();
+        assert!(
+            matches!(error, crate::Error::Credentials(ref reason) if reason == "no credentials")
+        );
+    }
+
+    #[test]
+    fn impersonated_service_account_credentials() {
+        let json = serde_json::json!({
+            "type": "impersonated_service_account",
+            "service_account_impersonation_url": "https://iamcredentials.googleapis.com/v1/projects/-/serviceAccounts/reader@my-project.iam.gserviceaccount.com:generateAccessToken",
+            "delegates": [],
+            "source_credentials": {
+                "type": "authorized_user",
+                "client_id": "client",
+                "client_secret": "secret",
+                "refresh_token": "refresh",
+            },
+        });
+        let client = Client::from_credentials_json(&json.to_string()).unwrap();
+        assert_eq!(client.project_id().unwrap(), "my-project");
+    }
+
+    // Fails the first `failures` requests with a `503 Service Unavailable`.
+    fn flaky_server(failures: usize) -> crate::test_server::TestServer {
+        use crate::test_server::{object_json, Response, TestServer};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let count = AtomicUsize::new(0);
+        TestServer::start(move |_| {
+            if count.fetch_add(1, Ordering::SeqCst) < failures {
+                Response::google_error(503, "backendError", "Backend Error")
+            } else {
+                Response::new(200).json(object_json("bucket", "file", &[0, 1]))
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn idempotent_requests_are_retried() -> Result<(), Box<dyn std::error::Error>> {
+        let server = flaky_server(2);
+        let object = server.client().object().read("bucket", "file").await?;
+        assert_eq!(object.name, "file");
+        assert_eq!(server.requests().len(), 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retries_stop_after_max_attempts() {
+        let server = flaky_server(usize::MAX);
+        let client = server
+            .client()
+            .with_retry_policy(RetryPolicy::none().max_attempts(2));
+        let error = client.object().read("bucket", "file").await.unwrap_err();
+        assert!(error.retryable(), "{:?}", error);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn mutations_are_only_retried_with_preconditions(
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let server = flaky_server(1);
+        let client = server.client();
+        let result = client
+            .object()
+            .create("bucket", vec![0, 1], "file", "text/plain")
+            .await;
+        assert!(result.unwrap_err().retryable());
+        assert_eq!(server.requests().len(), 1);
+
+        let server = flaky_server(1);
+        let client = server.client();
+        client
+            .object()
+            .with_preconditions(crate::Preconditions::does_not_exist())
+            .create("bucket", vec![0, 1], "file", "text/plain")
+            .await?;
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].body, vec![0, 1]);
+        Ok(())
+    }
+}
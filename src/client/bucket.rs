@@ -0,0 +1,354 @@
+use futures::{Stream, TryStreamExt};
+
+use crate::{
+    bucket::{IamPolicy, ListRequest, TestIamPermission},
+    error::GoogleResponse,
+    resources::common::ListResponse,
+    Bucket, Error, NewBucket,
+};
+
+/// Operations on `Bucket`s.
+pub struct BucketClient<'a>(pub(super) &'a super::Client);
+
+impl<'a> BucketClient<'a> {
+    /// Creates a new `Bucket` in the project of this client's service account. There are many
+    /// options that you can provide for creating a new bucket, so the `NewBucket` resource
+    /// contains all of them. Note that `NewBucket` implements `Default`, so you don't have to
+    /// specify the fields you're not using. And error is returned if that bucket name is already
+    /// taken.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::bucket::{Location, MultiRegion, NewBucket};
+    ///
+    /// let client = Client::default();
+    /// let new_bucket = NewBucket {
+    ///    name: "cloud-storage-rs-doc-1".to_string(), // this is the only mandatory field
+    ///    location: Location::Multi(MultiRegion::Eu),
+    ///    ..Default::default()
+    /// };
+    /// let bucket = client.bucket().create(&new_bucket).await?;
+    /// # client.bucket().delete(bucket).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(&self, new_bucket: &NewBucket) -> crate::Result<Bucket> {
+        let url = format!("{}/b/", self.0.endpoints.json_api);
+        let query: Vec<_> = self.0.project_id.iter().map(|p| ("project", p)).collect();
+        let request = self
+            .0
+            .client
+            .post(&url)
+            .headers(self.0.get_headers().await?)
+            .query(&query)
+            .json(new_bucket);
+        let result: GoogleResponse<Bucket> = self.0.send(request, true).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns all `Bucket`s within the project of this client's service account.
+    ///
+    /// ### Note
+    /// When using incorrect permissions, this function fails silently and returns an empty list.
+    ///
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let buckets = client.bucket().list().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list(&self) -> crate::Result<Vec<Bucket>> {
+        self.list_with(ListRequest::new()).await?.try_concat().await
+    }
+
+    /// Lists the `Bucket`s within the project of this client that are selected by `request`, one
+    /// page at a time. The next page is only requested once the previous one has been consumed.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{bucket::ListRequest, Client};
+    /// use futures::TryStreamExt;
+    ///
+    /// let client = Client::default();
+    /// let mut pages = Box::pin(client.bucket().list_with(ListRequest::new().prefix("logs-")).await?);
+    /// while let Some(buckets) = pages.try_next().await? {
+    ///     for bucket in buckets {
+    ///         println!("{}", bucket.name);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_with(
+        &self,
+        request: ListRequest,
+    ) -> crate::Result<impl Stream<Item = crate::Result<Vec<Bucket>>> + 'a> {
+        let url = format!("{}/b/", self.0.endpoints.json_api);
+        let mut query: Vec<_> = self
+            .0
+            .project_id
+            .iter()
+            .map(|p| ("project", p.clone()))
+            .collect();
+        query.extend(request.query());
+        Ok(self
+            .0
+            .list_pages(url, query, None)
+            .map_ok(|page: ListResponse<Bucket>| page.items))
+    }
+
+    /// Returns a single `Bucket` by its name. If the Bucket does not exist, an error is returned.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let bucket = client.bucket().read("cloud-storage-rs-doc-2").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read(&self, name: &str) -> crate::Result<Bucket> {
+        let url = format!("{}/b/{}", self.0.endpoints.json_api, name);
+        let request = self.0.client.get(&url).headers(self.0.get_headers().await?);
+        let result: GoogleResponse<Bucket> = self.0.send(request, true).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Update an existing `Bucket`. If you declare you bucket as mutable, you can edit its fields.
+    /// You can then flush your changes to Google Cloud Storage using this method.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::bucket::RetentionPolicy;
+    ///
+    /// let client = Client::default();
+    /// let mut bucket = client.bucket().read("cloud-storage-rs-doc-3").await?;
+    /// bucket.retention_policy = Some(RetentionPolicy {
+    ///     retention_period: 50,
+    ///     effective_time: chrono::Utc::now() + chrono::Duration::seconds(50),
+    ///     is_locked: Some(false),
+    /// });
+    /// client.bucket().update(&bucket).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn update(&self, bucket: &Bucket) -> crate::Result<Bucket> {
+        let url = format!("{}/b/{}", self.0.endpoints.json_api, bucket.name);
+        let request = self
+            .0
+            .client
+            .put(&url)
+            .headers(self.0.get_headers().await?)
+            .json(bucket);
+        let result: GoogleResponse<Bucket> = self.0.send(request, false).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Delete an existing `Bucket`. This permanently removes a bucket from Google Cloud Storage.
+    /// An error is returned when you don't have sufficient permissions, or when the
+    /// `retention_policy` prevents you from deleting your Bucket.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let bucket = client.bucket().read("unnecessary-bucket").await?;
+    /// client.bucket().delete(bucket).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn delete(&self, bucket: Bucket) -> crate::Result<()> {
+        let url = format!("{}/b/{}", self.0.endpoints.json_api, bucket.name);
+        let request = self
+            .0
+            .client
+            .delete(&url)
+            .headers(self.0.get_headers().await?);
+        let response = self.0.send(request, true).await?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::Google(response.json().await?))
+        }
+    }
+
+    /// Returns the [IAM Policy](https://cloud.google.com/iam/docs/) for this bucket.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let bucket = client.bucket().read("cloud-storage-rs-doc-4").await?;
+    /// let policy = client.bucket().get_iam_policy(&bucket).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_iam_policy(&self, bucket: &Bucket) -> crate::Result<IamPolicy> {
+        let url = format!("{}/b/{}/iam", self.0.endpoints.json_api, bucket.name);
+        let request = self.0.client.get(&url).headers(self.0.get_headers().await?);
+        let result: GoogleResponse<IamPolicy> = self.0.send(request, true).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Updates the [IAM Policy](https://cloud.google.com/iam/docs/) for this bucket.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::bucket::{IamPolicy, Binding, IamRole, StandardIamRole};
+    ///
+    /// let client = Client::default();
+    /// let bucket = client.bucket().read("cloud-storage-rs-doc-5").await?;
+    /// let iam_policy = IamPolicy {
+    ///     version: 1,
+    ///     bindings: vec![
+    ///         Binding {
+    ///             role: IamRole::Standard(StandardIamRole::ObjectViewer),
+    ///             members: vec!["allUsers".to_string()],
+    ///             condition: None,
+    ///         }
+    ///     ],
+    ///     ..Default::default()
+    /// };
+    /// let policy = client.bucket().set_iam_policy(&bucket, &iam_policy).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_iam_policy(
+        &self,
+        bucket: &Bucket,
+        iam: &IamPolicy,
+    ) -> crate::Result<IamPolicy> {
+        let url = format!("{}/b/{}/iam", self.0.endpoints.json_api, bucket.name);
+        let request = self
+            .0
+            .client
+            .put(&url)
+            .headers(self.0.get_headers().await?)
+            .json(iam);
+        let result: GoogleResponse<IamPolicy> = self.0.send(request, false).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Checks whether the user provided in the service account has this permission.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let bucket = client.bucket().read("my-bucket").await?;
+    /// client.bucket().test_iam_permission(&bucket, "storage.buckets.get").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn test_iam_permission(
+        &self,
+        bucket: &Bucket,
+        permission: &str,
+    ) -> crate::Result<TestIamPermission> {
+        if permission == "storage.buckets.list" || permission == "storage.buckets.create" {
+            return Err(Error::new(
+                "tested permission must not be `storage.buckets.list` or `storage.buckets.create`",
+            ));
+        }
+        let url = format!(
+            "{}/b/{}/iam/testPermissions",
+            self.0.endpoints.json_api, bucket.name
+        );
+        let request = self
+            .0
+            .client
+            .get(&url)
+            .headers(self.0.get_headers().await?)
+            .query(&[("permissions", permission)]);
+        let result: GoogleResponse<TestIamPermission> =
+            self.0.send(request, true).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{bucket_json, Response, TestServer};
+
+    #[tokio::test]
+    async fn list_follows_every_page() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            if request.path.contains("pageToken=page2") {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#buckets",
+                    "items": [bucket_json("logs-b")],
+                }))
+            } else {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#buckets",
+                    "items": [bucket_json("logs-a")],
+                    "nextPageToken": "page2",
+                }))
+            }
+        });
+        let request = ListRequest::new()
+            .prefix("logs-")
+            .max_results(1)
+            .projection(crate::bucket::Projection::Full);
+        let buckets = server
+            .client()
+            .bucket()
+            .list_with(request)
+            .await?
+            .try_concat()
+            .await?;
+        let names: Vec<_> = buckets.iter().map(|bucket| bucket.name.as_str()).collect();
+        assert_eq!(names, vec!["logs-a", "logs-b"]);
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].path,
+            "/storage/v1/b/?project=test-project&prefix=logs-&maxResults=1&projection=full"
+        );
+        assert_eq!(
+            requests[1].path,
+            "/storage/v1/b/?project=test-project&prefix=logs-&maxResults=1&projection=full&pageToken=page2"
+        );
+        assert_eq!(server.client().bucket().list().await?.len(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+/// The base urls a `Client` sends its requests to. The defaults point to Google Cloud Storage,
+/// `Endpoints::emulator` points all of them to a local emulator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    /// The root of the JSON API, to which `/b/{bucket}` is appended. Defaults to
+    /// `https://www.googleapis.com/storage/v1`.
+    pub json_api: String,
+    /// The root of the upload API, used to create objects. Defaults to
+    /// `https://www.googleapis.com/upload/storage/v1`.
+    pub upload: String,
+    /// The root of the XML API, used in signed urls. Defaults to `https://storage.googleapis.com`.
+    pub download: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            json_api: "https://www.googleapis.com/storage/v1".to_string(),
+            upload: "https://www.googleapis.com/upload/storage/v1".to_string(),
+            download: "https://storage.googleapis.com".to_string(),
+        }
+    }
+}
+
+impl Endpoints {
+    /// The endpoints of an emulator listening on `host`, for example `localhost:4443` or
+    /// `http://127.0.0.1:9023`. When no scheme is given, `http` is used.
+    pub fn emulator(host: &str) -> Self {
+        let host = host.trim_end_matches('/');
+        let base = if host.starts_with("http://") || host.starts_with("https://") {
+            host.to_string()
+        } else {
+            format!("http://{}", host)
+        };
+        Self {
+            json_api: format!("{}/storage/v1", base),
+            upload: format!("{}/upload/storage/v1", base),
+            download: base,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emulator_without_scheme() {
+        let endpoints = Endpoints::emulator("localhost:4443/");
+        assert_eq!(endpoints.json_api, "http://localhost:4443/storage/v1");
+        assert_eq!(endpoints.upload, "http://localhost:4443/upload/storage/v1");
+        assert_eq!(endpoints.download, "http://localhost:4443");
+    }
+
+    #[test]
+    fn emulator_with_scheme() {
+        let endpoints = Endpoints::emulator("https://gcs.local");
+        assert_eq!(endpoints.json_api, "https://gcs.local/storage/v1");
+        assert_eq!(endpoints.download, "https://gcs.local");
+    }
+}
@@ -0,0 +1,288 @@
+use futures::{Stream, TryStreamExt};
+
+use crate::{
+    error::GoogleResponse,
+    hmac_key::{HmacKey, HmacMeta, HmacState, ListRequest, UpdateMeta},
+    resources::common::ListResponse,
+};
+
+/// Operations on `HmacKey`s.
+pub struct HmacKeyClient<'a>(pub(super) &'a super::Client);
+
+impl<'a> HmacKeyClient<'a> {
+    /// Creates a new HMAC key for the service account of this client.
+    ///
+    /// The authenticated user must have `storage.hmacKeys.create` permission for the project in
+    /// which the key will be created.
+    ///
+    /// For general information about HMAC keys in Cloud Storage, see
+    /// [HMAC Keys](https://cloud.google.com/storage/docs/authentication/hmackeys).
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let hmac_key = client.hmac_key().create().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(&self) -> crate::Result<HmacKey> {
+        use reqwest::header::CONTENT_LENGTH;
+
+        let url = format!(
+            "{}/projects/{}/hmacKeys",
+            self.0.endpoints.json_api,
+            self.0.project_id()?
+        );
+        let service_account = match self.0.service_account {
+            Some(ref service_account) => service_account,
+            None => {
+                return Err(crate::Error::new(
+                    "creating an HmacKey requires a service account",
+                ))
+            }
+        };
+        let query = [("serviceAccountEmail", &service_account.client_email)];
+        let mut headers = self.0.get_headers().await?;
+        headers.insert(CONTENT_LENGTH, 0.into());
+        let request = self.0.client.post(&url).headers(headers).query(&query);
+        let result: GoogleResponse<HmacKey> = self.0.send(request, false).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Retrieves a list of HMAC keys matching the criteria. Since the HmacKey is secret, this does
+    /// not return a `HmacKey`, but a `HmacMeta`. This is a redacted version of a `HmacKey`, but
+    /// with the secret data omitted.
+    ///
+    /// The authenticated user must have `storage.hmacKeys.list` permission for the project in which
+    /// the key exists.
+    ///
+    /// For general information about HMAC keys in Cloud Storage, see
+    /// [HMAC Keys](https://cloud.google.com/storage/docs/authentication/hmackeys).
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let all_hmac_keys = client.hmac_key().list().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list(&self) -> crate::Result<Vec<HmacMeta>> {
+        self.list_with(ListRequest::new()).await?.try_concat().await
+    }
+
+    /// Lists the HMAC keys of the project of this client that are selected by `request`, one page
+    /// at a time. The next page is only requested once the previous one has been consumed.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{hmac_key::ListRequest, Client};
+    /// use futures::TryStreamExt;
+    ///
+    /// let client = Client::default();
+    /// let request = ListRequest::new().show_deleted_keys();
+    /// let mut pages = Box::pin(client.hmac_key().list_with(request).await?);
+    /// while let Some(keys) = pages.try_next().await? {
+    ///     for key in keys {
+    ///         println!("{} is {:?}", key.access_id, key.state);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_with(
+        &self,
+        request: ListRequest,
+    ) -> crate::Result<impl Stream<Item = crate::Result<Vec<HmacMeta>>> + 'a> {
+        let url = format!(
+            "{}/projects/{}/hmacKeys",
+            self.0.endpoints.json_api,
+            self.0.project_id()?
+        );
+        Ok(self
+            .0
+            .list_pages(url, request.query(), None)
+            .map_ok(|page: ListResponse<HmacMeta>| page.items))
+    }
+
+    /// Retrieves an HMAC key's metadata. Since the HmacKey is secret, this does not return a
+    /// `HmacKey`, but a `HmacMeta`. This is a redacted version of a `HmacKey`, but with the secret
+    /// data omitted.
+    ///
+    /// The authenticated user must have `storage.hmacKeys.get` permission for the project in which
+    /// the key exists.
+    ///
+    /// For general information about HMAC keys in Cloud Storage, see
+    /// [HMAC Keys](https://cloud.google.com/storage/docs/authentication/hmackeys).
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let key = client.hmac_key().read("some identifier").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read(&self, access_id: &str) -> crate::Result<HmacMeta> {
+        let url = format!(
+            "{}/projects/{}/hmacKeys/{}",
+            self.0.endpoints.json_api,
+            self.0.project_id()?,
+            access_id
+        );
+        let request = self.0.client.get(&url).headers(self.0.get_headers().await?);
+        let result: GoogleResponse<HmacMeta> = self.0.send(request, true).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Updates the state of an HMAC key. See the HMAC Key resource descriptor for valid states.
+    /// Since the HmacKey is secret, this does not return a `HmacKey`, but a `HmacMeta`. This is a
+    /// redacted version of a `HmacKey`, but with the secret data omitted.
+    ///
+    /// The authenticated user must have `storage.hmacKeys.update` permission for the project in
+    /// which the key exists.
+    ///
+    /// For general information about HMAC keys in Cloud Storage, see
+    /// [HMAC Keys](https://cloud.google.com/storage/docs/authentication/hmackeys).
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::hmac_key::HmacState;
+    ///
+    /// let client = Client::default();
+    /// let key = client.hmac_key().update("your key", HmacState::Active).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn update(&self, access_id: &str, state: HmacState) -> crate::Result<HmacMeta> {
+        let url = format!(
+            "{}/projects/{}/hmacKeys/{}",
+            self.0.endpoints.json_api,
+            self.0.project_id()?,
+            access_id
+        );
+        let request = self
+            .0
+            .client
+            .put(&url)
+            .headers(self.0.get_headers().await?)
+            .json(&UpdateMeta { state });
+        let result: GoogleResponse<HmacMeta> = self.0.send(request, false).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Deletes an HMAC key. Note that a key must be set to `Inactive` first.
+    ///
+    /// The authenticated user must have storage.hmacKeys.delete permission for the project in which
+    /// the key exists.
+    ///
+    /// For general information about HMAC keys in Cloud Storage, see
+    /// [HMAC Keys](https://cloud.google.com/storage/docs/authentication/hmackeys).
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::hmac_key::HmacState;
+    ///
+    /// let client = Client::default();
+    /// let key = client.hmac_key().update("your key", HmacState::Inactive).await?; // this is required.
+    /// client.hmac_key().delete(&key.access_id).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn delete(&self, access_id: &str) -> crate::Result<()> {
+        let url = format!(
+            "{}/projects/{}/hmacKeys/{}",
+            self.0.endpoints.json_api,
+            self.0.project_id()?,
+            access_id
+        );
+        let request = self
+            .0
+            .client
+            .delete(&url)
+            .headers(self.0.get_headers().await?);
+        let response = self.0.send(request, true).await?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(crate::Error::Google(response.json().await?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{hmac_meta_json, Response, TestServer};
+
+    #[tokio::test]
+    async fn list_with_sends_filters_and_pages() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            if request.path.contains("pageToken=page2") {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#hmacKeysMetadata",
+                    "items": [hmac_meta_json("GOOG2")],
+                }))
+            } else {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#hmacKeysMetadata",
+                    "items": [hmac_meta_json("GOOG1")],
+                    "nextPageToken": "page2",
+                }))
+            }
+        });
+        let request = ListRequest::new()
+            .service_account_email("robot@my-project.iam.gserviceaccount.com")
+            .show_deleted_keys()
+            .max_results(1);
+        let keys = server
+            .client()
+            .hmac_key()
+            .list_with(request)
+            .await?
+            .try_concat()
+            .await?;
+        let access_ids: Vec<_> = keys.iter().map(|key| key.access_id.as_str()).collect();
+        assert_eq!(access_ids, vec!["GOOG1", "GOOG2"]);
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].path,
+            "/storage/v1/projects/test-project/hmacKeys\
+             ?serviceAccountEmail=robot%40my-project.iam.gserviceaccount.com\
+             &showDeletedKeys=true&maxResults=1"
+        );
+        assert!(requests[1].path.ends_with("&maxResults=1&pageToken=page2"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_without_keys() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|_| {
+            Response::new(200).json(serde_json::json!({ "kind": "storage#hmacKeysMetadata" }))
+        });
+        assert!(server.client().hmac_key().list().await?.is_empty());
+        Ok(())
+    }
+}
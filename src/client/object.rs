@@ -0,0 +1,2266 @@
+use futures::{stream, Stream, TryStream, TryStreamExt};
+use reqwest::header::HeaderMap;
+
+use super::{ObjectStream, ResumableUpload};
+use crate::{
+    checksum::{Hashes, VerifyingStream},
+    error::{Error, GoogleResponse},
+    object::{
+        percent_encode, ComposeRequest, ListRequest, NewObject, ObjectList, RewriteOptions,
+        RewriteProgress, RewriteResponse, SizedByteStream,
+    },
+    resources::common::ListResponse,
+    ByteRange, Checksum, ContentRange, EncryptionKey, Object, PostPolicy, Preconditions,
+    SignedPostPolicy, SignedUrlOptions,
+};
+
+/// Operations on `Object`s.
+pub struct ObjectClient<'a> {
+    pub(super) client: &'a super::Client,
+    pub(super) checksum: Checksum,
+    pub(super) preconditions: Preconditions,
+    // the generation of the object, or of the source object of copies and rewrites
+    pub(super) generation: Option<i64>,
+    // the key of the object, or of the destination object of copies and rewrites
+    pub(super) encryption_key: Option<EncryptionKey>,
+    pub(super) source_encryption_key: Option<EncryptionKey>,
+}
+
+impl<'a> ObjectClient<'a> {
+    /// Selects which checksums are computed and verified by the uploads and downloads performed
+    /// through this `ObjectClient`. By default, CRC32C checksums are verified.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Checksum, Client};
+    ///
+    /// let client = Client::default();
+    /// // this file is checked by other means, skip the checksum
+    /// let bytes = client
+    ///     .object()
+    ///     .with_checksum(Checksum::None)
+    ///     .download("my_bucket", "huge-file.bin")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Sets the preconditions that must hold for the object that is created, updated, deleted,
+    /// composed, copied or rewritten through this `ObjectClient`. For copies and rewrites, the
+    /// preconditions apply to the destination object. If they do not hold, the request fails with
+    /// an error for which `Error::is_condition_not_met` returns `true`.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Client, Preconditions};
+    ///
+    /// let client = Client::default();
+    /// let mut object = client.object().read("my_bucket", "counter.txt").await?;
+    /// object.content_type = Some("text/plain".to_string());
+    /// let result = client
+    ///     .object()
+    ///     .with_preconditions(Preconditions::new().if_metageneration_match(object.metageneration))
+    ///     .update(&object)
+    ///     .await;
+    /// match result {
+    ///     Err(e) if e.is_condition_not_met() => println!("somebody else updated the object first"),
+    ///     result => println!("{:?}", result?),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_preconditions(mut self, preconditions: Preconditions) -> Self {
+        self.preconditions = preconditions;
+        self
+    }
+
+    /// Selects the generation of the object that is read, downloaded, updated or deleted through
+    /// this `ObjectClient`, instead of the live version. In buckets with versioning enabled, this
+    /// addresses noncurrent versions of an object. Copies and rewrites use this generation of the
+    /// source object.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let bytes = client
+    ///     .object()
+    ///     .with_generation(1588778400000000)
+    ///     .download("my_bucket", "config.json")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_generation(mut self, generation: i64) -> Self {
+        self.generation = Some(generation);
+        self
+    }
+
+    /// Encrypts the objects that are created, composed, copied or rewritten through this
+    /// `ObjectClient` with a customer-supplied encryption key, and decrypts the objects that are
+    /// read or downloaded with it. Objects that are encrypted with a customer-supplied key cannot
+    /// be downloaded without it.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Client, EncryptionKey};
+    ///
+    /// let client = Client::default();
+    /// let key = EncryptionKey::from_base64("X4Y1c9uhvAAtrN2KbNv4cC9tIX09iebVbLIvtE1YRcs=")?;
+    /// let object = client.object().with_encryption_key(key.clone());
+    /// object.create("my_bucket", b"secret".to_vec(), "secret.txt", "text/plain").await?;
+    /// let bytes = object.download("my_bucket", "secret.txt").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_encryption_key(mut self, key: EncryptionKey) -> Self {
+        self.encryption_key = Some(key);
+        self
+    }
+
+    /// Decrypts the source objects of copies and rewrites through this `ObjectClient` with a
+    /// customer-supplied encryption key. Together with `ObjectClient::with_encryption_key`, this
+    /// rotates the key of an object by rewriting it onto itself.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Client, EncryptionKey};
+    ///
+    /// let client = Client::default();
+    /// let old_key = EncryptionKey::from_base64("X4Y1c9uhvAAtrN2KbNv4cC9tIX09iebVbLIvtE1YRcs=")?;
+    /// let new_key = EncryptionKey::new([7; 32]);
+    /// let object = client.object().read("my_bucket", "secret.txt").await?;
+    /// client
+    ///     .object()
+    ///     .with_source_encryption_key(old_key)
+    ///     .with_encryption_key(new_key)
+    ///     .rewrite(&object, "my_bucket", "secret.txt")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_source_encryption_key(mut self, key: EncryptionKey) -> Self {
+        self.source_encryption_key = Some(key);
+        self
+    }
+
+    // Adds the headers of the key set with `with_encryption_key` and, for copies and rewrites,
+    // the key set with `with_source_encryption_key`.
+    fn add_encryption_headers(&self, headers: &mut HeaderMap, copy: bool) -> crate::Result<()> {
+        if let Some(ref key) = self.encryption_key {
+            key.add_headers(headers)?;
+        }
+        match self.source_encryption_key {
+            Some(ref key) if copy => key.add_source_headers(headers),
+            _ => Ok(()),
+        }
+    }
+
+    // The query parameter `name` with the generation selected by `with_generation`, if any.
+    fn generation(&self, name: &'static str) -> Vec<(&'static str, i64)> {
+        self.generation
+            .iter()
+            .map(|generation| (name, *generation))
+            .collect()
+    }
+
+    /// Create a new object.
+    /// Upload a file as that is loaded in memory to google cloud storage, where it will be
+    /// interpreted according to the mime type you specified.
+    /// ## Example
+    /// ```rust,no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # fn read_cute_cat(_in: &str) -> Vec<u8> { vec![0, 1] }
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let file: Vec<u8> = read_cute_cat("cat.png");
+    /// client.object().create("cat-photos", file, "recently read cat.png", "image/png").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create(
+        &self,
+        bucket: &str,
+        file: Vec<u8>,
+        filename: &str,
+        mime_type: &str,
+    ) -> crate::Result<Object> {
+        use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+
+        // has its own url for some reason
+        let url = &format!(
+            "{}/b/{}/o?uploadType=media&name={}",
+            self.client.endpoints.upload,
+            percent_encode(bucket),
+            percent_encode(filename),
+        );
+        let hashes = Hashes::compute(self.checksum, &file)?;
+        let mut headers = self.client.get_headers().await?;
+        self.add_encryption_headers(&mut headers, false)?;
+        headers.insert(CONTENT_TYPE, mime_type.parse()?);
+        headers.insert(CONTENT_LENGTH, file.len().to_string().parse()?);
+        if let Some(ref hashes) = hashes {
+            headers.insert("x-goog-hash", hashes.header_value().parse()?);
+        }
+        let request = self
+            .client
+            .client
+            .post(url)
+            .query(&self.preconditions)
+            .headers(headers)
+            .body(file);
+        let response = self
+            .client
+            .send(request, self.preconditions.pins_generation())
+            .await?;
+        if response.status() == 200 {
+            let object = serde_json::from_str(&response.text().await?)?;
+            verify_upload(hashes, &object)?;
+            Ok(object)
+        } else {
+            Err(Error::from_response(response).await)
+        }
+    }
+
+    /// Create a new object and set its metadata in the same request, using a
+    /// [multipart upload](https://cloud.google.com/storage/docs/uploading-objects#uploading-an-object).
+    /// The object is stored with the `content_type` of `new_object`, or as
+    /// `application/octet-stream` if it is not set.
+    /// ## Example
+    /// ```rust,no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::object::NewObject;
+    ///
+    /// let client = Client::default();
+    /// let mut metadata = std::collections::HashMap::new();
+    /// metadata.insert("owner".to_string(), "garfield".to_string());
+    /// let new_object = NewObject {
+    ///     name: "cat.png".to_string(),
+    ///     content_type: Some("image/png".to_string()),
+    ///     metadata: Some(metadata),
+    ///     ..Default::default()
+    /// };
+    /// client.object().create_with("cat-photos", vec![0, 1], &new_object).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_with(
+        &self,
+        bucket: &str,
+        file: Vec<u8>,
+        new_object: &NewObject,
+    ) -> crate::Result<Object> {
+        use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+
+        let url = &format!(
+            "{}/b/{}/o?uploadType=multipart",
+            self.client.endpoints.upload,
+            percent_encode(bucket),
+        );
+        let hashes = Hashes::compute(self.checksum, &file)?;
+        let mut metadata = serde_json::to_value(new_object)?;
+        if let Some(ref hashes) = hashes {
+            // Google rejects the upload if the data does not match these fields
+            let fields = [("crc32c", &hashes.crc32c), ("md5Hash", &hashes.md5)];
+            for (field, hash) in fields.iter() {
+                if let (Some(hash), None) = (hash, metadata.get(field)) {
+                    metadata[field] = serde_json::Value::String(hash.clone());
+                }
+            }
+        }
+        let metadata = serde_json::to_vec(&metadata)?;
+        let mime_type = new_object
+            .content_type
+            .as_deref()
+            .unwrap_or("application/octet-stream");
+        let (boundary, body) = multipart_related(&metadata, &file, mime_type);
+        let mut headers = self.client.get_headers().await?;
+        self.add_encryption_headers(&mut headers, false)?;
+        headers.insert(
+            CONTENT_TYPE,
+            format!("multipart/related; boundary={}", boundary).parse()?,
+        );
+        headers.insert(CONTENT_LENGTH, body.len().into());
+        let request = self
+            .client
+            .client
+            .post(url)
+            .query(&self.preconditions)
+            .headers(headers)
+            .body(body);
+        let response = self
+            .client
+            .send(request, self.preconditions.pins_generation())
+            .await?;
+        if response.status().is_success() {
+            let object = response.json().await?;
+            verify_upload(hashes, &object)?;
+            Ok(object)
+        } else {
+            Err(Error::from_response(response).await)
+        }
+    }
+
+    /// Create a new object. This works in the same way as `ObjectClient::create`, except it does
+    /// not need to load the entire file in ram. The file is sent using a resumable upload, so
+    /// chunks that are interrupted by a network failure are resumed instead of starting over.
+    /// ## Example
+    /// ```rust,no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let file = reqwest::Client::new()
+    ///     .get("https://my_domain.rs/nice_cat_photo.png")
+    ///     .send()
+    ///     .await?
+    ///     .bytes_stream();
+    /// client
+    ///     .object()
+    ///     .create_streamed("cat-photos", file, 10, "recently read cat.png", "image/png")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_streamed<S>(
+        &self,
+        bucket: &str,
+        stream: S,
+        length: impl Into<Option<u64>>,
+        filename: &str,
+        mime_type: &str,
+    ) -> crate::Result<Object>
+    where
+        S: TryStream + Send + Sync + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        bytes::Bytes: From<S::Ok>,
+    {
+        let length = length.into();
+        self.start_resumable_upload(bucket, filename, mime_type, length)
+            .await?
+            .upload(0, stream, length)
+            .await
+    }
+
+    /// Starts a [resumable upload](https://cloud.google.com/storage/docs/resumable-uploads)
+    /// session for a new object. The returned `ResumableUpload` can be used to upload the contents
+    /// in chunks, and its session uri can be stored to resume the upload later. If the size of
+    /// the object is known up front, pass it as `length` so Google can reject uploads that are too
+    /// large early.
+    /// ## Example
+    /// ```rust,no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let upload = client
+    ///     .object()
+    ///     .start_resumable_upload("my_bucket", "backup.tar", "application/x-tar", 3)
+    ///     .await?;
+    /// let status = upload.upload_chunk(0, vec![1, 2, 3], Some(3)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn start_resumable_upload(
+        &self,
+        bucket: &str,
+        filename: &str,
+        mime_type: &str,
+        length: impl Into<Option<u64>>,
+    ) -> crate::Result<ResumableUpload<'a>> {
+        use reqwest::header::{CONTENT_LENGTH, LOCATION};
+
+        let url = &format!(
+            "{}/b/{}/o?uploadType=resumable&name={}",
+            self.client.endpoints.upload,
+            percent_encode(bucket),
+            percent_encode(filename),
+        );
+        let mut headers = self.client.get_headers().await?;
+        self.add_encryption_headers(&mut headers, false)?;
+        headers.insert("X-Upload-Content-Type", mime_type.parse()?);
+        if let Some(length) = length.into() {
+            headers.insert("X-Upload-Content-Length", length.into());
+        }
+        headers.insert(CONTENT_LENGTH, 0.into());
+        let request = self
+            .client
+            .client
+            .post(url)
+            .query(&self.preconditions)
+            .headers(headers);
+        let response = self.client.send(request, true).await?;
+        if !response.status().is_success() {
+            return Err(Error::from_response(response).await);
+        }
+        let session_uri = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .ok_or_else(|| Error::new("resumable upload response has no Location header"))?;
+        Ok(ResumableUpload::new(
+            self.client,
+            session_uri.to_string(),
+            self.checksum,
+            self.encryption_key.clone(),
+        ))
+    }
+
+    /// Continues a resumable upload session that was started earlier, possibly by another
+    /// process, using the session uri returned by `ResumableUpload::session_uri`. Use
+    /// `ResumableUpload::status` to find the offset from which to continue.
+    pub fn resume_upload(&self, session_uri: &str) -> ResumableUpload<'a> {
+        ResumableUpload::new(
+            self.client,
+            session_uri.to_string(),
+            self.checksum,
+            self.encryption_key.clone(),
+        )
+    }
+
+    /// Creates a [Signed Url](https://cloud.google.com/storage/docs/access-control/signed-urls)
+    /// which is valid for `duration` seconds, and lets the posessor make the request described by
+    /// `options` for `file_name` in `bucket` without any authentication. The url is signed by the
+    /// `UrlSigner` of this client, so it works with a service account key as well as without one,
+    /// for example on Google Kubernetes Engine with workload identity.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Client, SignedUrlOptions};
+    ///
+    /// let client = Client::default();
+    /// let options = SignedUrlOptions::resumable_upload().content_type("video/mp4");
+    /// let url = client.object().signed_url("my_bucket", "videos/1.mp4", 600, &options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn signed_url(
+        &self,
+        bucket: &str,
+        file_name: &str,
+        duration: u32,
+        options: &SignedUrlOptions,
+    ) -> crate::Result<String> {
+        self.client
+            .url_signer()
+            .sign(bucket, file_name, duration, options)
+            .await
+    }
+
+    /// Signs a `PostPolicy` with the `UrlSigner` of this client, which lets browsers upload an
+    /// object with an HTML form for the next `duration` seconds without any authentication.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Client, PostPolicy};
+    ///
+    /// let client = Client::default();
+    /// let policy = PostPolicy::new("my_bucket", "avatar.png").content_type("image/png");
+    /// let signed = client.object().post_policy(&policy, 600).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn post_policy(
+        &self,
+        policy: &PostPolicy,
+        duration: u32,
+    ) -> crate::Result<SignedPostPolicy> {
+        self.client
+            .url_signer()
+            .sign_post_policy(policy, duration)
+            .await
+    }
+
+    /// Obtain a list of objects within this Bucket.
+    /// Every item of the stream is a whole page of objects; use `ObjectClient::list_stream` to list large
+    /// buckets one object at a time.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let all_objects = client.object().list("my_bucket").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list(
+        &self,
+        bucket: &'a str,
+    ) -> crate::Result<impl Stream<Item = crate::Result<Vec<Object>>> + 'a> {
+        let pages = self.list_with(bucket, ListRequest::new()).await?;
+        Ok(pages.map_ok(|page| page.items))
+    }
+
+    /// Obtain a list of objects by prefix within this Bucket .
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let all_objects = client.object().list_prefix("my_bucket", "prefix/").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_prefix(
+        &self,
+        bucket: &'a str,
+        prefix: &'a str,
+    ) -> crate::Result<impl Stream<Item = crate::Result<Vec<Object>>> + 'a> {
+        let pages = self
+            .list_with(bucket, ListRequest::new().prefix(prefix))
+            .await?;
+        Ok(pages.map_ok(|page| page.items))
+    }
+
+    /// Lists the objects in `bucket` that are selected by `request`, one page at a time. Each
+    /// page also contains the common prefixes of the object names when the request sets a
+    /// delimiter.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{object::ListRequest, Client};
+    /// use futures::TryStreamExt;
+    ///
+    /// let client = Client::default();
+    /// let request = ListRequest::new().prefix("photos/").delimiter("/");
+    /// let mut pages = Box::pin(client.object().list_with("my_bucket", request).await?);
+    /// while let Some(page) = pages.try_next().await? {
+    ///     for directory in page.prefixes {
+    ///         println!("{}", directory);
+    ///     }
+    ///     for object in page.items {
+    ///         println!("{}", object.name);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_with(
+        &self,
+        bucket: &'a str,
+        request: ListRequest,
+    ) -> crate::Result<impl Stream<Item = crate::Result<ObjectList>> + 'a> {
+        Ok(self.pages(bucket, request))
+    }
+
+    /// Lists the objects in `bucket` that are selected by `request`, one object at a time. Unlike
+    /// `ObjectClient::list`, the next page is only fetched once the objects of the previous page
+    /// have been consumed, so buckets of any size can be listed in constant memory. See
+    /// `ObjectStream` for how to checkpoint and resume the listing.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{object::ListRequest, Client};
+    /// use futures::TryStreamExt;
+    ///
+    /// let client = Client::default();
+    /// let mut objects = client.object().list_stream("my_bucket", ListRequest::new());
+    /// while let Some(object) = objects.try_next().await? {
+    ///     println!("{}", object.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_stream(&self, bucket: &'a str, request: ListRequest) -> ObjectStream<'a> {
+        let page_token = request.page_token.clone();
+        ObjectStream::new(self.pages(bucket, request), page_token)
+    }
+
+    fn pages(
+        &self,
+        bucket: &'a str,
+        request: ListRequest,
+    ) -> impl Stream<Item = crate::Result<ObjectList>> + Send + 'a {
+        let url = format!(
+            "{}/b/{}/o",
+            self.client.endpoints.json_api,
+            percent_encode(bucket)
+        );
+        self.client
+            .list_pages(url, request.query(), request.page_token)
+            .map_ok(|page: ListResponse<Object>| ObjectList {
+                items: page.items,
+                prefixes: page.prefixes,
+                next_page_token: page.next_page_token,
+            })
+    }
+
+    /// Obtains a single object with the specified name in the specified bucket.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let object = client.object().read("my_bucket", "path/to/my/file.png").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read(&self, bucket: &str, file_name: &str) -> crate::Result<Object> {
+        let url = format!(
+            "{}/b/{}/o/{}",
+            self.client.endpoints.json_api,
+            percent_encode(bucket),
+            percent_encode(file_name),
+        );
+        let mut headers = self.client.get_headers().await?;
+        self.add_encryption_headers(&mut headers, false)?;
+        let request = self
+            .client
+            .client
+            .get(&url)
+            .query(&self.generation("generation"))
+            .headers(headers);
+        let result: GoogleResponse<Object> = self.client.send(request, true).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Download the content of the object with the specified name in the specified bucket.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let bytes = client.object().download("my_bucket", "path/to/my/file.png").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download(&self, bucket: &str, file_name: &str) -> crate::Result<Vec<u8>> {
+        let response = self.media(bucket, file_name, None).await?;
+        let reported = reported_hashes(&response);
+        let bytes = response.bytes().await?.to_vec();
+        if let Some(reported) = reported {
+            if let Some(hashes) = Hashes::compute(self.checksum, &bytes)? {
+                hashes.verify(&reported)?;
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Download the content of the object with the specified name in the specified bucket, without
+    /// allocating the whole file into a vector.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use futures::StreamExt;
+    /// use std::fs::File;
+    /// use std::io::Write;
+    ///
+    /// let client = Client::default();
+    /// let mut stream = client.object().download_streamed("my_bucket", "path/to/my/file.png").await?;
+    /// let mut file = File::create("file.png").unwrap();
+    /// while let Some(byte) = stream.next().await {
+    ///     file.write_all(&[byte.unwrap()]).unwrap();
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_streamed(
+        &self,
+        bucket: &str,
+        file_name: &str,
+    ) -> crate::Result<impl Stream<Item = crate::Result<u8>> + Unpin> {
+        use futures::{StreamExt, TryStreamExt};
+        let res = self.media(bucket, file_name, None).await?;
+        let size = res.content_length();
+        let (hasher, reported) = match reported_hashes(&res) {
+            Some(reported) => (self.checksum.hasher()?, reported),
+            None => (None, Hashes::default()),
+        };
+        let bytes = VerifyingStream::new(res.bytes_stream(), hasher, reported)
+            .map(|chunk| chunk.map(|c| futures::stream::iter(c.into_iter().map(Ok))))
+            .try_flatten();
+        Ok(SizedByteStream::new(bytes, size))
+    }
+
+    /// Download part of the content of the object with the specified name in the specified bucket.
+    /// Returns the downloaded bytes, together with the range they cover and the total size of the
+    /// object. If the range starts beyond the end of the object, an error with reason
+    /// `Reason::RequestedRangeNotSatisfiable` is returned.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{ByteRange, Client};
+    ///
+    /// let client = Client::default();
+    /// let (bytes, range) = client
+    ///     .object()
+    ///     .download_range("my_bucket", "path/to/my/file.png", 0..1024)
+    ///     .await?;
+    /// let (footer, _) = client
+    ///     .object()
+    ///     .download_range("my_bucket", "data.parquet", ByteRange::Suffix(8))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_range(
+        &self,
+        bucket: &str,
+        file_name: &str,
+        range: impl Into<ByteRange>,
+    ) -> crate::Result<(Vec<u8>, ContentRange)> {
+        let response = self.media(bucket, file_name, Some(range.into())).await?;
+        let content_range = content_range(&response);
+        let bytes = response.bytes().await?.to_vec();
+        let content_range = content_range.unwrap_or_else(|| ContentRange::full(bytes.len() as u64));
+        Ok((bytes, content_range))
+    }
+
+    /// Download part of the content of the object with the specified name in the specified bucket,
+    /// without allocating it into a vector. The range that the stream covers is available through
+    /// `SizedByteStream::content_range`.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use futures::TryStreamExt;
+    ///
+    /// let client = Client::default();
+    /// let stream = client
+    ///     .object()
+    ///     .download_range_streamed("my_bucket", "big.log", 1024..)
+    ///     .await?;
+    /// let total_size = stream.content_range().and_then(|range| range.total_size);
+    /// let rest: Vec<u8> = stream.try_collect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_range_streamed(
+        &self,
+        bucket: &str,
+        file_name: &str,
+        range: impl Into<ByteRange>,
+    ) -> crate::Result<SizedByteStream<impl Stream<Item = crate::Result<u8>> + Unpin>> {
+        use futures::{StreamExt, TryStreamExt};
+
+        let response = self.media(bucket, file_name, Some(range.into())).await?;
+        let size = response.content_length();
+        let content_range = content_range(&response).or_else(|| size.map(ContentRange::full));
+        let bytes = response
+            .bytes_stream()
+            .map(|chunk| chunk.map(|c| futures::stream::iter(c.into_iter().map(Ok))))
+            .try_flatten();
+        let stream = SizedByteStream::new(bytes, size);
+        Ok(match content_range {
+            Some(content_range) => stream.with_content_range(content_range),
+            None => stream,
+        })
+    }
+
+    // Requests the contents of an object, or part of it, and turns unsuccessful responses into
+    // errors instead of returning the error body as the contents.
+    async fn media(
+        &self,
+        bucket: &str,
+        file_name: &str,
+        range: Option<ByteRange>,
+    ) -> crate::Result<reqwest::Response> {
+        use reqwest::header::RANGE;
+
+        let url = format!(
+            "{}/b/{}/o/{}?alt=media",
+            self.client.endpoints.json_api,
+            percent_encode(bucket),
+            percent_encode(file_name),
+        );
+        let mut headers = self.client.get_headers().await?;
+        self.add_encryption_headers(&mut headers, false)?;
+        if let Some(range) = range {
+            headers.insert(RANGE, range.header_value()?.parse()?);
+        }
+        let request = self
+            .client
+            .client
+            .get(&url)
+            .query(&self.generation("generation"))
+            .headers(headers);
+        let response = self.client.send(request, true).await?;
+        if response.status().is_success() {
+            Ok(response)
+        } else {
+            Err(Error::from_response(response).await)
+        }
+    }
+
+    /// Updates a single object with the specified name in the specified bucket with the new
+    /// information in `object`.
+    ///
+    /// Note that if the `name` or `bucket` fields are changed, the object will not be found.
+    /// See `ObjectClient::copy` or `ObjectClient::rewrite` for that.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let mut object = client.object().read("my_bucket", "path/to/my/file.png").await?;
+    /// object.content_type = Some("application/xml".to_string());
+    /// client.object().update(&object).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn update(&self, object: &Object) -> crate::Result<Object> {
+        let url = format!(
+            "{}/b/{}/o/{}",
+            self.client.endpoints.json_api,
+            percent_encode(&object.bucket),
+            percent_encode(&object.name),
+        );
+        let request = self
+            .client
+            .client
+            .put(&url)
+            .query(&self.preconditions)
+            .query(&self.generation("generation"))
+            .headers(self.client.get_headers().await?)
+            .json(&object);
+        let result: GoogleResponse<Object> = self
+            .client
+            .send(request, self.preconditions.pins_metageneration())
+            .await?
+            .json()
+            .await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Deletes a single object with the specified name in the specified bucket.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// client.object().delete("my_bucket", "path/to/my/file.png").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn delete(&self, bucket: &str, file_name: &str) -> crate::Result<()> {
+        let url = format!(
+            "{}/b/{}/o/{}",
+            self.client.endpoints.json_api,
+            percent_encode(bucket),
+            percent_encode(file_name),
+        );
+        let request = self
+            .client
+            .client
+            .delete(&url)
+            .query(&self.preconditions)
+            .query(&self.generation("generation"))
+            .headers(self.client.get_headers().await?);
+        // deleting a specific generation cannot delete another one when it is repeated
+        let idempotent = self.preconditions.pins_generation() || self.generation.is_some();
+        let response = self.client.send(request, idempotent).await?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(Error::from_response(response).await)
+        }
+    }
+
+    /// Concatenates the source objects into a new object in the specified bucket.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::object::{ComposeRequest, SourceObject};
+    ///
+    /// let client = Client::default();
+    /// let obj1 = client.object().read("my_bucket", "file1").await?;
+    /// let obj2 = client.object().read("my_bucket", "file2").await?;
+    /// let compose_request = ComposeRequest {
+    ///     kind: "storage#composeRequest".to_string(),
+    ///     source_objects: vec![
+    ///         SourceObject {
+    ///             name: obj1.name.clone(),
+    ///             generation: None,
+    ///             object_preconditions: None,
+    ///         },
+    ///         SourceObject {
+    ///             name: obj2.name.clone(),
+    ///             generation: None,
+    ///             object_preconditions: None,
+    ///         },
+    ///     ],
+    ///     destination: None,
+    /// };
+    /// let obj3 = client
+    ///     .object()
+    ///     .compose("my_bucket", &compose_request, "test-concatted-file")
+    ///     .await?;
+    /// // obj3 is now a file with the content of obj1 and obj2 concatted together.
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn compose(
+        &self,
+        bucket: &str,
+        req: &ComposeRequest,
+        destination_object: &str,
+    ) -> crate::Result<Object> {
+        let url = format!(
+            "{}/b/{}/o/{}/compose",
+            self.client.endpoints.json_api,
+            percent_encode(bucket),
+            percent_encode(destination_object)
+        );
+        let mut headers = self.client.get_headers().await?;
+        self.add_encryption_headers(&mut headers, false)?;
+        let request = self
+            .client
+            .client
+            .post(&url)
+            .query(&self.preconditions)
+            .headers(headers)
+            .json(req);
+        let result: GoogleResponse<Object> = self
+            .client
+            .send(request, self.preconditions.pins_generation())
+            .await?
+            .json()
+            .await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Copy `object` to the target bucket and path.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let obj1 = client.object().read("my_bucket", "file1").await?;
+    /// let obj2 = client.object().copy(&obj1, "my_other_bucket", "file2").await?;
+    /// // obj2 is now a copy of obj1.
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn copy(
+        &self,
+        object: &Object,
+        destination_bucket: &str,
+        path: &str,
+    ) -> crate::Result<Object> {
+        self.copy_object(&object.bucket, &object.name, destination_bucket, path)
+            .await
+    }
+
+    /// Restores a noncurrent `generation` of an object by copying it over the live version, which
+    /// becomes noncurrent itself. Unless other preconditions are set with
+    /// `ObjectClient::with_preconditions`, the copy only succeeds if the live version is still the
+    /// one that was found when the restore started, so concurrent writes are never overwritten. The
+    /// object is restored as well when it has been deleted.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{object::ListRequest, Client};
+    /// use futures::TryStreamExt;
+    ///
+    /// let client = Client::default();
+    /// let request = ListRequest::new().prefix("config.json").versions();
+    /// let versions: Vec<_> = client
+    ///     .object()
+    ///     .list_stream("my_bucket", request)
+    ///     .try_collect()
+    ///     .await?;
+    /// if let Some(oldest) = versions.iter().min_by_key(|version| version.generation) {
+    ///     client
+    ///         .object()
+    ///         .restore("my_bucket", "config.json", oldest.generation)
+    ///         .await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn restore(
+        &self,
+        bucket: &str,
+        file_name: &str,
+        generation: i64,
+    ) -> crate::Result<Object> {
+        let preconditions = if self.preconditions.is_empty() {
+            let live = ObjectClient {
+                preconditions: Preconditions::new(),
+                generation: None,
+                encryption_key: None,
+                source_encryption_key: None,
+                ..*self
+            };
+            match live.read(bucket, file_name).await {
+                Ok(object) => Preconditions::new().if_generation_match(object.generation),
+                Err(Error::Google(ref e)) if e.code() == 404 => Preconditions::does_not_exist(),
+                Err(e) => return Err(e),
+            }
+        } else {
+            self.preconditions
+        };
+        // the old generation was most likely encrypted with the same key as the restored one
+        let source_encryption_key = self
+            .source_encryption_key
+            .clone()
+            .or_else(|| self.encryption_key.clone());
+        ObjectClient {
+            preconditions,
+            generation: Some(generation),
+            encryption_key: self.encryption_key.clone(),
+            source_encryption_key,
+            ..*self
+        }
+        .copy_object(bucket, file_name, bucket, file_name)
+        .await
+    }
+
+    async fn copy_object(
+        &self,
+        bucket: &str,
+        file_name: &str,
+        destination_bucket: &str,
+        path: &str,
+    ) -> crate::Result<Object> {
+        use reqwest::header::CONTENT_LENGTH;
+
+        let url = format!(
+            "{base}/b/{sBucket}/o/{sObject}/copyTo/b/{dBucket}/o/{dObject}",
+            base = self.client.endpoints.json_api,
+            sBucket = percent_encode(bucket),
+            sObject = percent_encode(file_name),
+            dBucket = percent_encode(destination_bucket),
+            dObject = percent_encode(path),
+        );
+        let mut headers = self.client.get_headers().await?;
+        self.add_encryption_headers(&mut headers, true)?;
+        headers.insert(CONTENT_LENGTH, "0".parse()?);
+        let request = self
+            .client
+            .client
+            .post(&url)
+            .query(&self.preconditions)
+            .query(&self.generation("sourceGeneration"))
+            .headers(headers);
+        let result: GoogleResponse<Object> = self
+            .client
+            .send(request, self.preconditions.pins_generation())
+            .await?
+            .json()
+            .await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Rewrites `object` to the target bucket and path. Unlike `ObjectClient::copy`, a rewrite can
+    /// move objects between locations and storage classes, which Google may perform in several
+    /// calls. This function returns once the whole object has been rewritten.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let obj1 = client.object().read("my_bucket", "file1").await?;
+    /// let obj2 = client.object().rewrite(&obj1, "my_other_bucket", "file2").await?;
+    /// // obj2 is now a copy of obj1.
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn rewrite(
+        &self,
+        object: &Object,
+        destination_bucket: &str,
+        path: &str,
+    ) -> crate::Result<Object> {
+        self.rewrite_with(object, destination_bucket, path, &RewriteOptions::default())
+            .await
+    }
+
+    /// Rewrites `object` to the target bucket and path, changing its storage class or encryption
+    /// key as specified in `options`. This function returns once the whole object has been
+    /// rewritten.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::bucket::StorageClass;
+    /// use cloud_storage::object::RewriteOptions;
+    ///
+    /// let client = Client::default();
+    /// let obj1 = client.object().read("my_bucket", "file1").await?;
+    /// let opts = RewriteOptions::new().storage_class(StorageClass::Coldline);
+    /// let obj2 = client
+    ///     .object()
+    ///     .rewrite_with(&obj1, "my_archive_bucket", "file1", &opts)
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn rewrite_with(
+        &self,
+        object: &Object,
+        destination_bucket: &str,
+        path: &str,
+        options: &RewriteOptions,
+    ) -> crate::Result<Object> {
+        use futures::TryStreamExt;
+
+        let progress = self
+            .rewrite_progress(object, destination_bucket, path, options)
+            .await?;
+        let mut progress = Box::pin(progress);
+        while let Some(progress) = progress.try_next().await? {
+            if let Some(resource) = progress.resource {
+                return Ok(resource);
+            }
+        }
+        Err(Error::new(
+            "rewrite finished without returning the rewritten object",
+        ))
+    }
+
+    /// Rewrites `object` to the target bucket and path, yielding the progress of the rewrite
+    /// after every call to Google. The last item contains the rewritten object. The stream ends
+    /// after the first error.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    /// use cloud_storage::object::RewriteOptions;
+    /// use futures::TryStreamExt;
+    ///
+    /// let client = Client::default();
+    /// let obj1 = client.object().read("my_bucket", "file1").await?;
+    /// let opts = RewriteOptions::new().max_bytes_rewritten_per_call(64 * 1024 * 1024);
+    /// let progress = client
+    ///     .object()
+    ///     .rewrite_progress(&obj1, "my_other_bucket", "file2", &opts)
+    ///     .await?;
+    /// let mut progress = Box::pin(progress);
+    /// while let Some(progress) = progress.try_next().await? {
+    ///     println!("{} of {} bytes", progress.total_bytes_rewritten, progress.object_size);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn rewrite_progress(
+        &self,
+        object: &Object,
+        destination_bucket: &str,
+        path: &str,
+        options: &RewriteOptions,
+    ) -> crate::Result<impl Stream<Item = crate::Result<RewriteProgress>> + 'a> {
+        use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
+
+        let url = format!(
+            "{base}/b/{sBucket}/o/{sObject}/rewriteTo/b/{dBucket}/o/{dObject}",
+            base = self.client.endpoints.json_api,
+            sBucket = percent_encode(&object.bucket),
+            sObject = percent_encode(&object.name),
+            dBucket = percent_encode(destination_bucket),
+            dObject = percent_encode(path),
+        );
+        // a request body replaces the metadata of the rewritten object, so it is only sent when the
+        // storage class changes, and then contains the writable metadata of the source object. The
+        // encryption key is selected by `destinationKmsKeyName` and the encryption headers instead.
+        let body = match options.storage_class {
+            Some(ref storage_class) => {
+                let metadata = NewObject {
+                    name: path.to_string(),
+                    content_type: object.content_type.clone(),
+                    content_encoding: object.content_encoding.clone(),
+                    content_disposition: object.content_disposition.clone(),
+                    content_language: object.content_language.clone(),
+                    cache_control: object.cache_control.clone(),
+                    metadata: object.metadata.clone(),
+                    storage_class: Some(storage_class.clone()),
+                    ..NewObject::default()
+                };
+                Some(serde_json::to_vec(&metadata)?)
+            }
+            None => None,
+        };
+        let mut query = Vec::new();
+        if let Some(generation) = self.generation {
+            query.push(("sourceGeneration", generation.to_string()));
+        }
+        if let Some(ref kms_key_name) = options.kms_key_name {
+            query.push(("destinationKmsKeyName", kms_key_name.clone()));
+        }
+        if let Some(max_bytes) = options.max_bytes_rewritten_per_call {
+            query.push(("maxBytesRewrittenPerCall", max_bytes.to_string()));
+        }
+
+        let mut encryption_headers = HeaderMap::new();
+        self.add_encryption_headers(&mut encryption_headers, true)?;
+
+        let client = self.client;
+        let preconditions = self.preconditions;
+        // the state is the query of the next call, or `None` once the rewrite is done
+        Ok(stream::try_unfold(Some(query), move |query| {
+            let url = url.clone();
+            let body = body.clone();
+            let encryption_headers = encryption_headers.clone();
+            async move {
+                let mut query = match query {
+                    Some(query) => query,
+                    None => return Ok(None),
+                };
+                let mut headers = client.get_headers().await?;
+                headers.extend(encryption_headers);
+                let request = client.client.post(&url).query(&preconditions).query(&query);
+                let request = match body {
+                    Some(body) => {
+                        headers.insert(CONTENT_TYPE, "application/json".parse()?);
+                        request.body(body)
+                    }
+                    None => {
+                        headers.insert(CONTENT_LENGTH, 0.into());
+                        request
+                    }
+                };
+                let response = client
+                    .send(request.headers(headers), preconditions.pins_generation())
+                    .await?;
+                if !response.status().is_success() {
+                    return Err(Error::from_response(response).await);
+                }
+                let response: RewriteResponse = response.json().await?;
+                let next = match (response.done, response.rewrite_token) {
+                    (true, _) => None,
+                    (false, Some(rewrite_token)) => {
+                        query.retain(|(name, _)| *name != "rewriteToken");
+                        query.push(("rewriteToken", rewrite_token));
+                        Some(query)
+                    }
+                    (false, None) => {
+                        return Err(Error::new(
+                            "unfinished rewrite response has no rewriteToken",
+                        ))
+                    }
+                };
+                let progress = RewriteProgress {
+                    total_bytes_rewritten: response.total_bytes_rewritten,
+                    object_size: response.object_size,
+                    resource: response.resource,
+                };
+                Ok(Some((progress, next)))
+            }
+        }))
+    }
+}
+
+// The range of the object that a successful media response contains. A `200 OK` response, which
+// Google sends when the range covers the whole object, has no `Content-Range` header.
+fn content_range(response: &reqwest::Response) -> Option<ContentRange> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(ContentRange::parse)
+}
+
+// The checksums Google reports for a media response, or `None` if the response does not contain
+// the object exactly as it is stored, because it is partial or was decompressed while serving.
+fn reported_hashes(response: &reqwest::Response) -> Option<Hashes> {
+    let headers = response.headers();
+    let stored_encoding = headers
+        .get("x-goog-stored-content-encoding")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("identity");
+    let encoding = headers
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("identity");
+    if response.status() != reqwest::StatusCode::OK || stored_encoding != encoding {
+        return None;
+    }
+    Some(Hashes::from_headers(headers))
+}
+
+// Compares the checksums computed over uploaded data with the checksums of the created object.
+pub(super) fn verify_upload(hashes: Option<Hashes>, object: &Object) -> crate::Result<()> {
+    match hashes {
+        Some(hashes) => hashes.verify(&Hashes::from_object(object)),
+        None => Ok(()),
+    }
+}
+
+// Builds a `multipart/related` body with a JSON part containing the metadata, followed by the
+// contents of the object. Returns the boundary that separates the parts, which is chosen such
+// that it does not occur in the contents.
+fn multipart_related(metadata: &[u8], file: &[u8], mime_type: &str) -> (String, Vec<u8>) {
+    let mut boundary = "cloud_storage_rs_boundary".to_string();
+    while file
+        .windows(boundary.len())
+        .any(|window| window == boundary.as_bytes())
+    {
+        boundary.push('_');
+    }
+
+    let mut body = Vec::with_capacity(metadata.len() + file.len() + 4 * boundary.len() + 128);
+    body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+    body.extend_from_slice(b"Content-Type: application/json; charset=UTF-8\r\n\r\n");
+    body.extend_from_slice(metadata);
+    body.extend_from_slice(format!("\r\n--{}\r\n", boundary).as_bytes());
+    body.extend_from_slice(format!("Content-Type: {}\r\n\r\n", mime_type).as_bytes());
+    body.extend_from_slice(file);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    (boundary, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_server::{object_json, Response, TestServer},
+        Reason,
+    };
+
+    #[test]
+    fn multipart_boundary_does_not_occur_in_contents() {
+        let file = b"--cloud_storage_rs_boundary";
+        let (boundary, body) = multipart_related(b"{}", file, "text/plain");
+        assert_eq!(boundary, "cloud_storage_rs_boundary_");
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with("--cloud_storage_rs_boundary_\r\n"));
+        assert!(body.ends_with("--cloud_storage_rs_boundary_--\r\n"));
+    }
+
+    #[tokio::test]
+    async fn create_with_sends_metadata_and_data() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|_| {
+            Response::new(200).json(object_json("bucket", "cat.png", &[0, 1]))
+        });
+        let new_object = NewObject {
+            name: "cat.png".to_string(),
+            content_type: Some("image/png".to_string()),
+            cache_control: Some("no-cache".to_string()),
+            ..Default::default()
+        };
+        server
+            .client()
+            .object()
+            .create_with("bucket", vec![0, 1], &new_object)
+            .await?;
+
+        let request = &server.requests()[0];
+        assert_eq!(
+            request.path,
+            "/upload/storage/v1/b/bucket/o?uploadType=multipart"
+        );
+        assert_eq!(
+            request.header("Content-Type"),
+            Some("multipart/related; boundary=cloud_storage_rs_boundary")
+        );
+        let mut expected = b"--cloud_storage_rs_boundary\r\n\
+            Content-Type: application/json; charset=UTF-8\r\n\r\n\
+            {\"cacheControl\":\"no-cache\",\"contentType\":\"image/png\",\"crc32c\":\"Awr00Q==\",\"name\":\"cat.png\"}\r\n\
+            --cloud_storage_rs_boundary\r\n\
+            Content-Type: image/png\r\n\r\n"
+            .to_vec();
+        expected.extend_from_slice(&[0, 1]);
+        expected.extend_from_slice(b"\r\n--cloud_storage_rs_boundary--\r\n");
+        assert_eq!(request.body, expected);
+        Ok(())
+    }
+
+    // Serves the ten byte object `0123456789`, honouring the Range header like Google does.
+    fn ranged_server() -> TestServer {
+        TestServer::start(|request| {
+            let data = b"0123456789";
+            let len = data.len() as u64;
+            let range = match request.header("Range") {
+                Some(range) => range.trim_start_matches("bytes="),
+                None => return Response::new(200).body(&data[..]),
+            };
+            let (start, end) = range.split_at(range.find('-').unwrap());
+            let (start, end) = match (start.parse::<u64>(), end[1..].parse::<u64>()) {
+                (Ok(start), Ok(end)) => (start, (end + 1).min(len)),
+                (Ok(start), Err(_)) => (start, len),
+                (Err(_), Ok(suffix)) => (len.saturating_sub(suffix), len),
+                _ => unreachable!(),
+            };
+            if start >= len {
+                return Response::google_error(
+                    416,
+                    "requestedRangeNotSatisfiable",
+                    "The requested range cannot be satisfied.",
+                );
+            }
+            if start == 0 && end == len {
+                return Response::new(200).body(&data[..]);
+            }
+            Response::new(206)
+                .header(
+                    "Content-Range",
+                    &format!("bytes {}-{}/{}", start, end - 1, len),
+                )
+                .body(&data[start as usize..end as usize])
+        })
+    }
+
+    #[tokio::test]
+    async fn download_range() -> Result<(), Box<dyn std::error::Error>> {
+        let server = ranged_server();
+        let client = server.client();
+        let object = client.object();
+
+        let (bytes, range) = object.download_range("bucket", "file", 2..5).await?;
+        assert_eq!(bytes, b"234");
+        assert_eq!((range.start, range.end, range.total_size), (2, 5, Some(10)));
+
+        let (bytes, range) = object.download_range("bucket", "file", 7..).await?;
+        assert_eq!(bytes, b"789");
+        assert_eq!(range.total_size, Some(10));
+
+        let (bytes, range) = object
+            .download_range("bucket", "file", ByteRange::Suffix(4))
+            .await?;
+        assert_eq!(bytes, b"6789");
+        assert_eq!(range.start, 6);
+
+        let (bytes, range) = object.download_range("bucket", "file", 0..100).await?;
+        assert_eq!(bytes, b"0123456789");
+        assert_eq!(range, ContentRange::full(10));
+
+        let ranges: Vec<_> = server
+            .requests()
+            .iter()
+            .map(|r| r.header("Range").unwrap().to_string())
+            .collect();
+        assert_eq!(
+            ranges,
+            vec!["bytes=2-4", "bytes=7-", "bytes=-4", "bytes=0-99"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_range_streamed() -> Result<(), Box<dyn std::error::Error>> {
+        use futures::TryStreamExt;
+
+        let server = ranged_server();
+        let client = server.client();
+        let stream = client
+            .object()
+            .download_range_streamed("bucket", "file", 3..6)
+            .await?;
+        assert_eq!(stream.size_hint(), (3, Some(3)));
+        assert_eq!(stream.content_range().unwrap().total_size, Some(10));
+        let bytes: Vec<u8> = stream.try_collect().await?;
+        assert_eq!(bytes, b"345");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_range_not_satisfiable() {
+        let server = ranged_server();
+        let client = server.client();
+        let result = client.object().download_range("bucket", "file", 20..).await;
+        assert_google_error(result, 416, Reason::RequestedRangeNotSatisfiable);
+    }
+
+    // Serves the error responses for a missing object, missing permissions and a failed
+    // precondition. Like Google, the 404 is sent as plain text.
+    fn error_server() -> TestServer {
+        TestServer::start(|request| match request.path.split('?').next().unwrap() {
+            "/storage/v1/b/bucket/o/missing" => {
+                Response::new(404).body("No such object: bucket/missing")
+            }
+            "/storage/v1/b/bucket/o/secret" => Response::google_error(
+                403,
+                "forbidden",
+                "anonymous caller does not have storage.objects.get access",
+            ),
+            _ => Response::google_error(412, "conditionNotMet", "Precondition Failed"),
+        })
+    }
+
+    fn assert_google_error<T: std::fmt::Debug>(
+        result: crate::Result<T>,
+        code: u16,
+        reason: Reason,
+    ) {
+        match result {
+            Err(Error::Google(e)) => {
+                assert_eq!(e.code(), code);
+                assert!(e.errors_has_reason(&reason), "{:?}", e);
+            }
+            other => panic!("expected a {} error, got {:?}", code, other),
+        }
+    }
+
+    #[tokio::test]
+    async fn download_error_status() {
+        let server = error_server();
+        let client = server.client();
+        let object = client.object();
+        assert_google_error(
+            object.download("bucket", "missing").await,
+            404,
+            Reason::NotFound,
+        );
+        assert_google_error(
+            object.download("bucket", "secret").await,
+            403,
+            Reason::Forbidden,
+        );
+        assert_google_error(
+            object.download("bucket", "changed").await,
+            412,
+            Reason::ConditionNotMet,
+        );
+    }
+
+    #[tokio::test]
+    async fn download_streamed_error_status() {
+        let server = error_server();
+        let client = server.client();
+        let cases = [
+            ("missing", 404, Reason::NotFound),
+            ("secret", 403, Reason::Forbidden),
+            ("changed", 412, Reason::ConditionNotMet),
+        ];
+        for (name, code, reason) in cases {
+            let result = client.object().download_streamed("bucket", name).await;
+            assert_google_error(result.map(|_| ()), code, reason);
+        }
+    }
+
+    #[tokio::test]
+    async fn create_sends_checksums() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            Response::new(200).json(object_json("bucket", "file", &request.body))
+        });
+        let client = server.client();
+        client
+            .object()
+            .with_checksum(Checksum::Crc32cAndMd5)
+            .create("bucket", b"hello world".to_vec(), "file", "text/plain")
+            .await?;
+        client
+            .object()
+            .with_checksum(Checksum::None)
+            .create("bucket", b"hello world".to_vec(), "file", "text/plain")
+            .await?;
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].header("x-goog-hash"),
+            Some("crc32c=yZRlqg==,md5=XrY7u+Ae7tCTyyK7j1rNww==")
+        );
+        assert_eq!(requests[1].header("x-goog-hash"), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_detects_corrupted_upload() {
+        // the server stores something other than what was sent
+        let server =
+            TestServer::start(|_| Response::new(200).json(object_json("bucket", "file", b"hello")));
+        let client = server.client();
+        let result = client
+            .object()
+            .create("bucket", b"hello world".to_vec(), "file", "text/plain")
+            .await;
+        match result {
+            Err(Error::ChecksumMismatch { algorithm, .. }) => assert_eq!(algorithm, "crc32c"),
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn create_with_sets_checksum_metadata() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|_| {
+            Response::new(200).json(object_json("bucket", "file", b"hello world"))
+        });
+        let new_object = NewObject {
+            name: "file".to_string(),
+            ..Default::default()
+        };
+        server
+            .client()
+            .object()
+            .create_with("bucket", b"hello world".to_vec(), &new_object)
+            .await?;
+        let body = String::from_utf8(server.requests()[0].body.clone())?;
+        assert!(
+            body.contains(r#"{"crc32c":"yZRlqg==","name":"file"}"#),
+            "{}",
+            body
+        );
+        Ok(())
+    }
+
+    // Serves `hello world` with the checksums of `hello there`, like a corrupted download.
+    fn corrupted_server() -> TestServer {
+        TestServer::start(|_| {
+            Response::new(200)
+                .header(
+                    "x-goog-hash",
+                    "crc32c=Mek6pA==,md5=FhYP3gAQ3NBPqGVrQAcKSw==",
+                )
+                .body("hello world")
+        })
+    }
+
+    #[tokio::test]
+    async fn download_detects_corruption() -> Result<(), Box<dyn std::error::Error>> {
+        let server = corrupted_server();
+        let client = server.client();
+        match client.object().download("bucket", "file").await {
+            Err(Error::ChecksumMismatch { algorithm, .. }) => assert_eq!(algorithm, "crc32c"),
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+        let bytes = client
+            .object()
+            .with_checksum(Checksum::None)
+            .download("bucket", "file")
+            .await?;
+        assert_eq!(bytes, b"hello world");
+        // partial responses are not verified
+        let (bytes, _) = client
+            .object()
+            .download_range("bucket", "file", 0..5)
+            .await?;
+        assert_eq!(bytes, b"hello world");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn download_streamed_detects_corruption() -> Result<(), Box<dyn std::error::Error>> {
+        use futures::TryStreamExt;
+
+        let server = corrupted_server();
+        let client = server.client();
+        let stream = client.object().download_streamed("bucket", "file").await?;
+        match stream.try_collect::<Vec<u8>>().await {
+            Err(Error::ChecksumMismatch { algorithm, .. }) => assert_eq!(algorithm, "crc32c"),
+            other => panic!("expected a checksum mismatch, got {:?}", other),
+        }
+        let stream = client
+            .object()
+            .with_checksum(Checksum::None)
+            .download_streamed("bucket", "file")
+            .await?;
+        assert_eq!(stream.try_collect::<Vec<u8>>().await?, b"hello world");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn preconditions_are_sent() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| match request.method.as_str() {
+            "DELETE" => Response::new(204),
+            _ => Response::new(200).json(object_json("bucket", "file", &[0, 1])),
+        });
+        let client = server.client();
+        let object: Object = serde_json::from_value(object_json("bucket", "file", &[0, 1]))?;
+        client
+            .object()
+            .with_preconditions(Preconditions::does_not_exist())
+            .create("bucket", vec![0, 1], "file", "text/plain")
+            .await?;
+        client
+            .object()
+            .with_preconditions(Preconditions::new().if_metageneration_match(1))
+            .update(&object)
+            .await?;
+        client
+            .object()
+            .with_preconditions(Preconditions::new().if_generation_match(7))
+            .delete("bucket", "file")
+            .await?;
+        client.object().delete("bucket", "file").await?;
+
+        let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/upload/storage/v1/b/bucket/o?uploadType=media&name=file&ifGenerationMatch=0",
+                "/storage/v1/b/bucket/o/file?ifMetagenerationMatch=1",
+                "/storage/v1/b/bucket/o/file?ifGenerationMatch=7",
+                "/storage/v1/b/bucket/o/file",
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn failed_precondition() {
+        // emulators may not send a JSON error body
+        let server = TestServer::start(|_| Response::new(412).body("Precondition Failed"));
+        let client = server.client();
+        let object = client
+            .object()
+            .with_preconditions(Preconditions::does_not_exist());
+        let result = object
+            .create("bucket", vec![0, 1], "file", "text/plain")
+            .await;
+        assert!(result.unwrap_err().is_condition_not_met());
+        let result = object.delete("bucket", "file").await;
+        assert!(result.unwrap_err().is_condition_not_met());
+        assert!(!Error::new("Precondition Failed").is_condition_not_met());
+    }
+
+    // Rewrites objects in two calls, like Google does for rewrites between storage classes.
+    fn rewrite_server() -> TestServer {
+        TestServer::start(|request| {
+            if request.path.contains("rewriteToken=token") {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#rewriteResponse",
+                    "totalBytesRewritten": "2",
+                    "objectSize": "2",
+                    "done": true,
+                    "resource": object_json("archive", "file", &[0, 1]),
+                }))
+            } else {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#rewriteResponse",
+                    "totalBytesRewritten": "1",
+                    "objectSize": "2",
+                    "done": false,
+                    "rewriteToken": "token",
+                }))
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn rewrite_continues_until_done() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::bucket::StorageClass;
+
+        let server = rewrite_server();
+        let client = server.client();
+        let mut object = object_json("bucket", "file", &[0, 1]);
+        object["cacheControl"] = "no-cache".into();
+        object["metadata"] = serde_json::json!({ "owner": "me" });
+        let object: Object = serde_json::from_value(object)?;
+        let options = RewriteOptions::new()
+            .storage_class(StorageClass::Coldline)
+            .kms_key_name("projects/p/locations/l/keyRings/r/cryptoKeys/k")
+            .max_bytes_rewritten_per_call(1048576);
+        let rewritten = client
+            .object()
+            .rewrite_with(&object, "archive", "file", &options)
+            .await?;
+        assert_eq!(rewritten.bucket, "archive");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let query =
+            "destinationKmsKeyName=projects%2Fp%2Flocations%2Fl%2FkeyRings%2Fr%2FcryptoKeys%2Fk\
+            &maxBytesRewrittenPerCall=1048576";
+        assert_eq!(
+            requests[0].path,
+            format!(
+                "/storage/v1/b/bucket/o/file/rewriteTo/b/archive/o/file?{}",
+                query
+            )
+        );
+        assert_eq!(
+            requests[1].path,
+            format!(
+                "/storage/v1/b/bucket/o/file/rewriteTo/b/archive/o/file?{}&rewriteToken=token",
+                query
+            )
+        );
+        // only the writable metadata of the source object is sent
+        for request in &requests {
+            let body: serde_json::Value = serde_json::from_slice(&request.body)?;
+            assert_eq!(
+                body,
+                serde_json::json!({
+                    "name": "file",
+                    "contentType": "text/plain",
+                    "cacheControl": "no-cache",
+                    "metadata": { "owner": "me" },
+                    "storageClass": "COLDLINE",
+                })
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rewrite_reports_progress() -> Result<(), Box<dyn std::error::Error>> {
+        use futures::TryStreamExt;
+
+        let server = rewrite_server();
+        let client = server.client();
+        let object: Object = serde_json::from_value(object_json("bucket", "file", &[0, 1]))?;
+        let progress: Vec<_> = client
+            .object()
+            .rewrite_progress(&object, "archive", "file", &RewriteOptions::new())
+            .await?
+            .try_collect()
+            .await?;
+        let bytes: Vec<_> = progress
+            .iter()
+            .map(|p| (p.total_bytes_rewritten, p.object_size, p.resource.is_some()))
+            .collect();
+        assert_eq!(bytes, [(1, 2, false), (2, 2, true)]);
+        // without a storage class, the metadata of the source object is kept
+        assert!(server.requests().iter().all(|r| r.body.is_empty()));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn signed_url_binds_headers() -> Result<(), Box<dyn std::error::Error>> {
+        let (service_account, key) = crate::test_server::service_account();
+        let client = crate::Client::with_service_account(service_account);
+        let options = SignedUrlOptions::put()
+            .content_type("image/png")
+            .metadata("Owner", " Alice ")
+            .content_length_range(0, 100);
+        let url = client
+            .object()
+            .signed_url("bucket", "dir/a b.png", 300, &options)
+            .await?;
+
+        let (resource, query) = url.split_once('?').unwrap();
+        assert_eq!(
+            resource,
+            "https://storage.googleapis.com/bucket/dir/a%20b.png"
+        );
+        let (query, signature) = query.rsplit_once("&X-Goog-Signature=").unwrap();
+        let param = |name: &str| {
+            let value = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix(&format!("{}=", name)))
+                .unwrap();
+            percent_encoding::percent_decode_str(value)
+                .decode_utf8()
+                .unwrap()
+                .into_owned()
+        };
+        let signed_headers = "content-type;host;x-goog-content-length-range;x-goog-meta-owner";
+        assert_eq!(param("X-Goog-SignedHeaders"), signed_headers);
+        assert_eq!(param("X-Goog-Expires"), "300");
+
+        // recompute the string to sign, and verify the signature with the public key
+        let canonical_request = format!(
+            "PUT\n/bucket/dir/a%20b.png\n{}\n\
+             content-type:image/png\n\
+             host:storage.googleapis.com\n\
+             x-goog-content-length-range:0,100\n\
+             x-goog-meta-owner:Alice\n\n{}\nUNSIGNED-PAYLOAD",
+            query, signed_headers
+        );
+        let credential = param("X-Goog-Credential");
+        let scope = credential.split_once('/').unwrap().1;
+        let string_to_sign = format!(
+            "GOOG4-RSA-SHA256\n{}\n{}\n{}",
+            param("X-Goog-Date"),
+            scope,
+            hex::encode(openssl::sha::sha256(canonical_request.as_bytes()))
+        );
+        let public_key = openssl::pkey::PKey::from_rsa(key)?;
+        let mut verifier =
+            openssl::sign::Verifier::new(openssl::hash::MessageDigest::sha256(), &public_key)?;
+        verifier.update(string_to_sign.as_bytes())?;
+        assert!(verifier.verify(&hex::decode(signature).unwrap())?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn signed_url_requires_service_account() {
+        let client = crate::Client::emulator("localhost:4443");
+        let url = client
+            .object()
+            .signed_url("bucket", "file", 60, &SignedUrlOptions::delete())
+            .await;
+        assert!(matches!(url, Err(Error::Credentials(_))));
+    }
+
+    #[tokio::test]
+    async fn list_with_sends_parameters_and_pages() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            if request.path.contains("pageToken=page2") {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#objects",
+                    "items": [object_json("bucket", "photos/c.png", b"c")],
+                }))
+            } else {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#objects",
+                    "prefixes": ["photos/2019/", "photos/2020/"],
+                    "items": [object_json("bucket", "photos/a.png", b"a")],
+                    "nextPageToken": "page2",
+                }))
+            }
+        });
+        let request = ListRequest::new()
+            .prefix("photos/")
+            .delimiter("/")
+            .include_trailing_delimiter()
+            .versions()
+            .start_offset("photos/a")
+            .end_offset("photos/z")
+            .match_glob("**.png")
+            .max_results(2)
+            .projection(crate::object::Projection::Full);
+        let pages: Vec<ObjectList> = server
+            .client()
+            .object()
+            .list_with("bucket", request)
+            .await?
+            .try_collect()
+            .await?;
+
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].prefixes, ["photos/2019/", "photos/2020/"]);
+        assert_eq!(pages[0].items[0].name, "photos/a.png");
+        assert_eq!(pages[0].next_page_token.as_deref(), Some("page2"));
+        assert!(pages[1].prefixes.is_empty());
+        assert_eq!(pages[1].items[0].name, "photos/c.png");
+        assert_eq!(pages[1].next_page_token, None);
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].path,
+            "/storage/v1/b/bucket/o?prefix=photos%2F&delimiter=%2F&startOffset=photos%2Fa\
+             &endOffset=photos%2Fz&matchGlob=**.png&includeTrailingDelimiter=true&versions=true\
+             &maxResults=2&projection=full"
+        );
+        assert!(requests[1]
+            .path
+            .ends_with("&projection=full&pageToken=page2"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_with_starts_at_page_token() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|_| {
+            Response::new(200).json(serde_json::json!({ "kind": "storage#objects" }))
+        });
+        let pages: Vec<ObjectList> = server
+            .client()
+            .object()
+            .list_with("bucket", ListRequest::new().page_token("resume"))
+            .await?
+            .try_collect()
+            .await?;
+        assert_eq!(pages.len(), 1);
+        assert!(pages[0].items.is_empty());
+        assert_eq!(
+            server.requests()[0].path,
+            "/storage/v1/b/bucket/o?pageToken=resume"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_stream_fetches_pages_on_demand() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            if request.path.contains("pageToken=page2") {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#objects",
+                    "items": [object_json("bucket", "c", b"c")],
+                }))
+            } else {
+                Response::new(200).json(serde_json::json!({
+                    "kind": "storage#objects",
+                    "items": [object_json("bucket", "a", b"a"), object_json("bucket", "b", b"b")],
+                    "nextPageToken": "page2",
+                }))
+            }
+        });
+        let client = server.client();
+        let mut objects = client
+            .object()
+            .list_stream("bucket", ListRequest::new().prefix("p"));
+        assert!(server.requests().is_empty());
+
+        assert_eq!(objects.try_next().await?.unwrap().name, "a");
+        assert_eq!(objects.page_token(), None);
+        assert_eq!(objects.try_next().await?.unwrap().name, "b");
+        // the first page is consumed, so a checkpoint resumes at the second one
+        assert_eq!(objects.page_token(), Some("page2"));
+        assert_eq!(server.requests().len(), 1);
+
+        assert_eq!(objects.try_next().await?.unwrap().name, "c");
+        assert_eq!(objects.page_token(), None);
+        assert!(objects.try_next().await?.is_none());
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].path, "/storage/v1/b/bucket/o?prefix=p");
+        assert_eq!(
+            requests[1].path,
+            "/storage/v1/b/bucket/o?prefix=p&pageToken=page2"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_stream_resumes_at_page_token() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|_| {
+            Response::new(200).json(serde_json::json!({
+                "kind": "storage#objects",
+                "items": [object_json("bucket", "c", b"c")],
+            }))
+        });
+        let client = server.client();
+        let objects = client
+            .object()
+            .list_stream("bucket", ListRequest::new().page_token("page2"));
+        assert_eq!(objects.page_token(), Some("page2"));
+        let names: Vec<String> = objects.map_ok(|object| object.name).try_collect().await?;
+        assert_eq!(names, vec!["c"]);
+        assert_eq!(
+            server.requests()[0].path,
+            "/storage/v1/b/bucket/o?pageToken=page2"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn generation_is_sent() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            if request.path.contains("alt=media") {
+                Response::new(200).body("old")
+            } else if request.method == "DELETE" {
+                Response::new(204)
+            } else {
+                Response::new(200).json(object_json("bucket", "file", b"old"))
+            }
+        });
+        let client = server.client();
+        let object = client
+            .object()
+            .with_generation(7)
+            .read("bucket", "file")
+            .await?;
+        let bytes = client
+            .object()
+            .with_checksum(crate::Checksum::None)
+            .with_generation(7)
+            .download("bucket", "file")
+            .await?;
+        assert_eq!(bytes, b"old");
+        client
+            .object()
+            .with_generation(7)
+            .copy(&object, "other", "file")
+            .await?;
+        client
+            .object()
+            .with_generation(7)
+            .delete("bucket", "file")
+            .await?;
+
+        let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "/storage/v1/b/bucket/o/file?generation=7",
+                "/storage/v1/b/bucket/o/file?alt=media&generation=7",
+                "/storage/v1/b/bucket/o/file/copyTo/b/other/o/file?sourceGeneration=7",
+                "/storage/v1/b/bucket/o/file?generation=7",
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restore_pins_the_live_generation() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|_| {
+            // `object_json` describes generation 1
+            Response::new(200).json(object_json("bucket", "file", b"new"))
+        });
+        server
+            .client()
+            .object()
+            .restore("bucket", "file", 7)
+            .await?;
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/storage/v1/b/bucket/o/file");
+        assert_eq!(
+            requests[1].path,
+            "/storage/v1/b/bucket/o/file/copyTo/b/bucket/o/file?ifGenerationMatch=1&sourceGeneration=7"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn restore_deleted_object() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| match request.method.as_str() {
+            "GET" => Response::google_error(404, "notFound", "No such object: bucket/file"),
+            _ => Response::new(200).json(object_json("bucket", "file", b"old")),
+        });
+        server
+            .client()
+            .object()
+            .restore("bucket", "file", 7)
+            .await?;
+        assert_eq!(
+            server.requests()[1].path,
+            "/storage/v1/b/bucket/o/file/copyTo/b/bucket/o/file?ifGenerationMatch=0&sourceGeneration=7"
+        );
+
+        // the preconditions of the client replace the check of the live generation
+        server
+            .client()
+            .object()
+            .with_preconditions(Preconditions::new().if_metageneration_match(3))
+            .restore("bucket", "file", 7)
+            .await?;
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            requests[2].path,
+            "/storage/v1/b/bucket/o/file/copyTo/b/bucket/o/file?ifMetagenerationMatch=3&sourceGeneration=7"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encryption_keys_are_sent() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|request| {
+            if request.path.contains("alt=media") {
+                Response::new(200).body("secret")
+            } else {
+                Response::new(200).json(object_json("bucket", "file", b"secret"))
+            }
+        });
+        let client = server.client();
+        let key = EncryptionKey::new([1; 32]);
+        let old_key = EncryptionKey::new([2; 32]);
+        let object = client.object().with_encryption_key(key.clone());
+        let created = object
+            .create("bucket", b"secret".to_vec(), "file", "text/plain")
+            .await?;
+        object.read("bucket", "file").await?;
+        object
+            .with_checksum(Checksum::None)
+            .download("bucket", "file")
+            .await?;
+        client
+            .object()
+            .with_encryption_key(key.clone())
+            .with_source_encryption_key(old_key.clone())
+            .copy(&created, "bucket", "copy")
+            .await?;
+        // restores decrypt the old generation with the same key, unless another one is set
+        client
+            .object()
+            .with_encryption_key(key.clone())
+            .restore("bucket", "file", 7)
+            .await?;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 6);
+        for (i, request) in requests.iter().enumerate() {
+            if i == 4 {
+                // `restore` reads the generation of the live object, which needs no key
+                assert_eq!(request.header("x-goog-encryption-key"), None);
+                continue;
+            }
+            assert_eq!(
+                request.header("x-goog-encryption-algorithm"),
+                Some("AES256")
+            );
+            assert_eq!(
+                request.header("x-goog-encryption-key-sha256"),
+                Some(key.key_sha256())
+            );
+        }
+        assert_eq!(
+            requests[3].header("x-goog-copy-source-encryption-key-sha256"),
+            Some(old_key.key_sha256())
+        );
+        assert_eq!(
+            requests[5].header("x-goog-copy-source-encryption-key-sha256"),
+            Some(key.key_sha256())
+        );
+        assert_eq!(
+            requests[0].header("x-goog-copy-source-encryption-key"),
+            None
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn rotate_key_with_rewrite() -> Result<(), Box<dyn std::error::Error>> {
+        let server = rewrite_server();
+        let client = server.client();
+        let mut object = object_json("bucket", "file", &[0, 1]);
+        object["customerEncryption"] = serde_json::json!({
+            "encryptionAlgorithm": "AES256",
+            "keySha256": EncryptionKey::new([2; 32]).key_sha256(),
+        });
+        let object: Object = serde_json::from_value(object)?;
+        let options = RewriteOptions::new().storage_class(crate::bucket::StorageClass::Coldline);
+        client
+            .object()
+            .with_source_encryption_key(EncryptionKey::new([2; 32]))
+            .with_encryption_key(EncryptionKey::new([1; 32]))
+            .rewrite_with(&object, "bucket", "file", &options)
+            .await?;
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        for request in &requests {
+            assert_eq!(
+                request.header("x-goog-encryption-key-sha256"),
+                Some(EncryptionKey::new([1; 32]).key_sha256())
+            );
+            assert_eq!(
+                request.header("x-goog-copy-source-encryption-key-sha256"),
+                Some(EncryptionKey::new([2; 32]).key_sha256())
+            );
+            let body: serde_json::Value = serde_json::from_slice(&request.body)?;
+            assert!(body.get("customerEncryption").is_none());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    error::GoogleResponse,
+    object_access_control::{Entity, NewObjectAccessControl, ObjectAccessControl},
+    resources::common::ListResponse,
+};
+
+/// Operations on `ObjectAccessControl`s.
+pub struct ObjectAccessControlClient<'a>(pub(super) &'a super::Client, pub(super) Option<i64>);
+
+impl<'a> ObjectAccessControlClient<'a> {
+    /// Selects the generation of the object whose ACL entries are created, read, updated and
+    /// deleted through this `ObjectAccessControlClient`, instead of the live version.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Client;
+    ///
+    /// let client = Client::default();
+    /// let acls = client
+    ///     .object_access_control()
+    ///     .with_generation(1588778400000000)
+    ///     .list("my_bucket", "config.json")
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_generation(mut self, generation: i64) -> Self {
+        self.1 = Some(generation);
+        self
+    }
+
+    // The `generation` query parameter selected by `with_generation`, if any.
+    fn generation(&self) -> Vec<(&'static str, i64)> {
+        self.1
+            .iter()
+            .map(|generation| ("generation", *generation))
+            .collect()
+    }
+
+    /// Creates a new ACL entry on the specified `object`.
+    ///
+    /// ### Important
+    /// This method fails with a 400 Bad Request response for buckets with uniform
+    /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
+    /// control access instead.
+    pub async fn create(
+        &self,
+        bucket: &str,
+        object: &str,
+        new_object_access_control: &NewObjectAccessControl,
+    ) -> crate::Result<ObjectAccessControl> {
+        let url = format!(
+            "{}/b/{}/o/{}/acl",
+            self.0.endpoints.json_api, bucket, object
+        );
+        let request = self
+            .0
+            .client
+            .post(&url)
+            .query(&self.generation())
+            .headers(self.0.get_headers().await?)
+            .json(new_object_access_control);
+        let result: GoogleResponse<ObjectAccessControl> =
+            self.0.send(request, false).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Retrieves `ACL` entries on the specified object.
+    ///
+    /// ### Important
+    /// Important: This method fails with a 400 Bad Request response for buckets with uniform
+    /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
+    /// control access instead.
+    pub async fn list(
+        &self,
+        bucket: &str,
+        object: &str,
+    ) -> crate::Result<Vec<ObjectAccessControl>> {
+        let url = format!(
+            "{}/b/{}/o/{}/acl",
+            self.0.endpoints.json_api, bucket, object
+        );
+        let request = self
+            .0
+            .client
+            .get(&url)
+            .query(&self.generation())
+            .headers(self.0.get_headers().await?);
+        let result: GoogleResponse<ListResponse<ObjectAccessControl>> =
+            self.0.send(request, true).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s.items),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns the `ACL` entry for the specified entity on the specified bucket.
+    ///
+    /// ### Important
+    /// Important: This method fails with a 400 Bad Request response for buckets with uniform
+    /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
+    /// control access instead.
+    pub async fn read(
+        &self,
+        bucket: &str,
+        object: &str,
+        entity: &Entity,
+    ) -> crate::Result<ObjectAccessControl> {
+        let url = format!(
+            "{}/b/{}/o/{}/acl/{}",
+            self.0.endpoints.json_api, bucket, object, entity
+        );
+        let request = self
+            .0
+            .client
+            .get(&url)
+            .query(&self.generation())
+            .headers(self.0.get_headers().await?);
+        let result: GoogleResponse<ObjectAccessControl> =
+            self.0.send(request, true).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Updates an ACL entry on the specified object.
+    ///
+    /// ### Important
+    /// Important: This method fails with a 400 Bad Request response for buckets with uniform
+    /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
+    /// control access instead.
+    pub async fn update(
+        &self,
+        object_access_control: &ObjectAccessControl,
+    ) -> crate::Result<ObjectAccessControl> {
+        let url = format!(
+            "{}/b/{}/o/{}/acl/{}",
+            self.0.endpoints.json_api,
+            object_access_control.bucket,
+            object_access_control.object,
+            object_access_control.entity,
+        );
+        let request = self
+            .0
+            .client
+            .put(&url)
+            .query(&self.generation())
+            .headers(self.0.get_headers().await?)
+            .json(object_access_control);
+        let result: GoogleResponse<ObjectAccessControl> =
+            self.0.send(request, false).await?.json().await?;
+        match result {
+            GoogleResponse::Success(s) => Ok(s),
+            GoogleResponse::Error(e) => Err(e.into()),
+        }
+    }
+
+    /// Permanently deletes the ACL entry for the specified entity on the specified object.
+    ///
+    /// ### Important
+    /// Important: This method fails with a 400 Bad Request response for buckets with uniform
+    /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
+    /// control access instead.
+    pub async fn delete(&self, object_access_control: ObjectAccessControl) -> crate::Result<()> {
+        let url = format!(
+            "{}/b/{}/o/{}/acl/{}",
+            self.0.endpoints.json_api,
+            object_access_control.bucket,
+            object_access_control.object,
+            object_access_control.entity,
+        );
+        let request = self
+            .0
+            .client
+            .delete(&url)
+            .query(&self.generation())
+            .headers(self.0.get_headers().await?);
+        let response = self.0.send(request, false).await?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(crate::Error::Google(response.json().await?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_server::{Response, TestServer};
+
+    #[tokio::test]
+    async fn generation_is_sent() -> Result<(), Box<dyn std::error::Error>> {
+        let server = TestServer::start(|_| {
+            Response::new(200).json(serde_json::json!({ "kind": "storage#objectAccessControls" }))
+        });
+        let acls = server
+            .client()
+            .object_access_control()
+            .with_generation(7)
+            .list("bucket", "file")
+            .await?;
+        assert!(acls.is_empty());
+        assert_eq!(
+            server.requests()[0].path,
+            "/storage/v1/b/bucket/o/file/acl?generation=7"
+        );
+        Ok(())
+    }
+}
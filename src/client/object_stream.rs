@@ -0,0 +1,92 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{ready, Stream};
+
+use crate::{object::ObjectList, Object};
+
+/// A stream of the objects in a bucket, as returned by `ObjectClient::list_stream`. Pages are only
+/// fetched when the objects of the previous page have been consumed, so at most a single page of
+/// objects is held in memory.
+///
+/// The listing can be checkpointed with `ObjectStream::page_token`, and resumed later by passing
+/// that token to `ListRequest::page_token`.
+/// ### Example
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use cloud_storage::{object::ListRequest, Client};
+/// use futures::TryStreamExt;
+///
+/// let client = Client::default();
+/// # let checkpoint: Option<String> = None;
+/// let mut request = ListRequest::new();
+/// if let Some(page_token) = checkpoint {
+///     request = request.page_token(&page_token);
+/// }
+/// let mut objects = client.object().list_stream("my_bucket", request);
+/// while let Some(object) = objects.try_next().await? {
+///     println!("{}", object.name);
+///     if let Some(page_token) = objects.page_token() {
+///         // store `page_token` somewhere
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ObjectStream<'a> {
+    pages: Pin<Box<dyn Stream<Item = crate::Result<ObjectList>> + Send + 'a>>,
+    items: std::vec::IntoIter<Object>,
+    page_token: Option<String>,
+    next_page_token: Option<String>,
+}
+
+impl<'a> ObjectStream<'a> {
+    pub(crate) fn new(
+        pages: impl Stream<Item = crate::Result<ObjectList>> + Send + 'a,
+        page_token: Option<String>,
+    ) -> Self {
+        Self {
+            pages: Box::pin(pages),
+            items: Vec::new().into_iter(),
+            page_token,
+            next_page_token: None,
+        }
+    }
+
+    /// The token of the page that the next object is taken from. It is `None` while that is the
+    /// first page, and after the last object has been yielded. A listing that is resumed from this
+    /// token yields every object that this stream has not yielded yet, but may repeat objects of
+    /// the current page that it already yielded.
+    pub fn page_token(&self) -> Option<&str> {
+        self.page_token.as_deref()
+    }
+}
+
+impl Stream for ObjectStream<'_> {
+    type Item = crate::Result<Object>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(object) = self.items.next() {
+                if self.items.as_slice().is_empty() {
+                    self.page_token = self.next_page_token.clone();
+                }
+                return Poll::Ready(Some(Ok(object)));
+            }
+            match ready!(self.pages.as_mut().poll_next(cx)) {
+                Some(Ok(page)) => {
+                    if page.items.is_empty() {
+                        self.page_token = page.next_page_token.clone();
+                    }
+                    self.items = page.items.into_iter();
+                    self.next_page_token = page.next_page_token;
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
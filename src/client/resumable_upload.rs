@@ -0,0 +1,599 @@
+use futures::{TryStream, TryStreamExt};
+use reqwest::{
+    header::{CONTENT_LENGTH, CONTENT_RANGE, RANGE},
+    StatusCode,
+};
+
+use crate::{checksum::Hashes, Checksum, EncryptionKey, Error, Object};
+
+/// The size in bytes that every chunk of a resumable upload, except the last one, must be a
+/// multiple of.
+pub const CHUNK_SIZE_MULTIPLE: usize = 256 * 1024;
+
+/// The chunk size that is used when none is set with `ResumableUpload::with_chunk_size`.
+pub const DEFAULT_CHUNK_SIZE: usize = 32 * CHUNK_SIZE_MULTIPLE;
+
+/// The state of a resumable upload, as reported by Google Cloud Storage.
+#[derive(Debug)]
+pub enum UploadStatus {
+    /// The upload is not finished yet, and this many bytes have been committed. The next chunk
+    /// must start at this offset.
+    InProgress(u64),
+    /// All bytes have been received and the object has been created.
+    Complete(Box<Object>),
+}
+
+/// A [resumable upload](https://cloud.google.com/storage/docs/resumable-uploads) session. A
+/// session is identified by its session uri, which can be persisted and used to resume the upload
+/// from another process with `ObjectClient::resume_upload`. Sessions expire after a week.
+/// ### Example
+/// ```no_run
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use cloud_storage::Client;
+/// use cloud_storage::client::UploadStatus;
+///
+/// let client = Client::default();
+/// let upload = client
+///     .object()
+///     .start_resumable_upload("my_bucket", "big-file.bin", "application/octet-stream", None)
+///     .await?;
+/// let session_uri = upload.session_uri().to_string(); // store this somewhere
+///
+/// // later, possibly in another process
+/// let upload = client.object().resume_upload(&session_uri);
+/// if let UploadStatus::InProgress(offset) = upload.status().await? {
+///     # let file = futures::stream::once(async { Ok::<_, cloud_storage::Error>(vec![0u8]) });
+///     // skip the first `offset` bytes of the file and upload the rest
+///     let object = upload.upload(offset, file, None).await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ResumableUpload<'a> {
+    client: &'a super::Client,
+    session_uri: String,
+    chunk_size: usize,
+    checksum: Checksum,
+    encryption_key: Option<EncryptionKey>,
+}
+
+impl<'a> ResumableUpload<'a> {
+    pub(crate) fn new(
+        client: &'a super::Client,
+        session_uri: String,
+        checksum: Checksum,
+        encryption_key: Option<EncryptionKey>,
+    ) -> Self {
+        Self {
+            client,
+            session_uri,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            checksum,
+            encryption_key,
+        }
+    }
+
+    /// The uri that identifies this session.
+    pub fn session_uri(&self) -> &str {
+        &self.session_uri
+    }
+
+    /// Sets the number of bytes that `ResumableUpload::upload` sends per request. The size is
+    /// rounded up to a multiple of `CHUNK_SIZE_MULTIPLE`, and defaults to `DEFAULT_CHUNK_SIZE`.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        let remainder = chunk_size % CHUNK_SIZE_MULTIPLE;
+        let chunk_size = match remainder {
+            0 => chunk_size,
+            _ => chunk_size + CHUNK_SIZE_MULTIPLE - remainder,
+        };
+        self.chunk_size = chunk_size.max(CHUNK_SIZE_MULTIPLE);
+        self
+    }
+
+    /// Selects which checksums `ResumableUpload::upload` computes over the uploaded data. They are
+    /// sent along with the last chunk, so Google rejects the upload if the data was corrupted.
+    /// Checksums are only computed when the upload starts at offset 0, because the data that was
+    /// uploaded before resuming is not available.
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    /// Asks Google Cloud Storage how many bytes of this upload have been committed, or returns the
+    /// created object if the upload has already finished.
+    pub async fn status(&self) -> crate::Result<UploadStatus> {
+        let request = self
+            .client
+            .client
+            .put(&self.session_uri)
+            .headers(self.client.get_headers().await?)
+            .header(CONTENT_RANGE, "bytes */*")
+            .header(CONTENT_LENGTH, 0);
+        let response = self.client.send(request, true).await?;
+        Self::parse_status(response).await
+    }
+
+    /// Uploads a single chunk that starts at byte `offset` of the object. Unless this is the last
+    /// chunk, the length of `chunk` must be a multiple of `CHUNK_SIZE_MULTIPLE`. Pass the total
+    /// size of the object as `total_size` once it is known, the upload is finished when all
+    /// bytes up to `total_size` have been received. An empty `chunk` with a `total_size`
+    /// finalizes the upload.
+    ///
+    /// Note that Google Cloud Storage may commit fewer bytes than were sent, the returned status
+    /// contains the offset from which the next chunk must start.
+    pub async fn upload_chunk(
+        &self,
+        offset: u64,
+        chunk: Vec<u8>,
+        total_size: Option<u64>,
+    ) -> crate::Result<UploadStatus> {
+        self.put_chunk(offset, chunk, total_size, None).await
+    }
+
+    async fn put_chunk(
+        &self,
+        offset: u64,
+        chunk: Vec<u8>,
+        total_size: Option<u64>,
+        hashes: Option<&Hashes>,
+    ) -> crate::Result<UploadStatus> {
+        let total = match total_size {
+            Some(total) => total.to_string(),
+            None => "*".to_string(),
+        };
+        let range = if chunk.is_empty() {
+            format!("bytes */{}", total)
+        } else {
+            format!(
+                "bytes {}-{}/{}",
+                offset,
+                offset + chunk.len() as u64 - 1,
+                total
+            )
+        };
+        let mut headers = self.client.get_headers().await?;
+        if let Some(ref key) = self.encryption_key {
+            key.add_headers(&mut headers)?;
+        }
+        if let Some(hashes) = hashes {
+            headers.insert("x-goog-hash", hashes.header_value().parse()?);
+        }
+        let response = self
+            .client
+            .client
+            .put(&self.session_uri)
+            .headers(headers)
+            .header(CONTENT_RANGE, range)
+            .header(CONTENT_LENGTH, chunk.len())
+            .body(chunk)
+            .send()
+            .await?;
+        Self::parse_status(response).await
+    }
+
+    /// Uploads the contents of `stream`, which must start at byte `offset` of the object, in
+    /// chunks of the configured chunk size. When a chunk is interrupted by a network failure or a
+    /// server error, the committed offset is queried and the chunk is resumed from there. Pass
+    /// `offset` 0 for a new session, or the offset returned by `ResumableUpload::status` when
+    /// resuming a session.
+    pub async fn upload<S>(
+        &self,
+        offset: u64,
+        stream: S,
+        total_size: Option<u64>,
+    ) -> crate::Result<Object>
+    where
+        S: TryStream,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        bytes::Bytes: From<S::Ok>,
+    {
+        let stream = stream
+            .map_ok(bytes::Bytes::from)
+            .map_err(|e| Error::Other(e.into().to_string()));
+        futures::pin_mut!(stream);
+
+        let mut hasher = if offset == 0 {
+            self.checksum.hasher()?
+        } else {
+            None
+        };
+        let mut offset = offset;
+        let mut buffer = Vec::with_capacity(self.chunk_size);
+        let mut finished = false;
+        loop {
+            while !finished && buffer.len() < self.chunk_size {
+                match stream.try_next().await? {
+                    Some(bytes) => {
+                        if let Some(hasher) = hasher.as_mut() {
+                            hasher.update(&bytes);
+                        }
+                        buffer.extend_from_slice(&bytes)
+                    }
+                    None => finished = true,
+                }
+            }
+            let hashes = match hasher.take() {
+                Some(hasher) if finished => Some(hasher.finish()?),
+                unfinished => {
+                    hasher = unfinished;
+                    None
+                }
+            };
+            let (chunk, total) = if finished {
+                let total = offset + buffer.len() as u64;
+                if let Some(expected) = total_size {
+                    if expected != total {
+                        let msg = format!("expected {} bytes, but got {}", expected, total);
+                        return Err(Error::Other(msg));
+                    }
+                }
+                (std::mem::take(&mut buffer), Some(total))
+            } else {
+                let rest = buffer.split_off(self.chunk_size);
+                (std::mem::replace(&mut buffer, rest), total_size)
+            };
+
+            match self
+                .send_resuming(offset, &chunk, total, hashes.as_ref())
+                .await?
+            {
+                UploadStatus::Complete(object) => {
+                    if let Some(hashes) = hashes {
+                        hashes.verify(&Hashes::from_object(&object))?;
+                    }
+                    return Ok(*object);
+                }
+                UploadStatus::InProgress(_) if finished => {
+                    return Err(Error::new("upload did not complete after the last chunk"));
+                }
+                UploadStatus::InProgress(committed) => offset = committed,
+            }
+        }
+    }
+
+    /// Cancels this upload session. Bytes that have been uploaded so far are discarded.
+    pub async fn cancel(self) -> crate::Result<()> {
+        let response = self
+            .client
+            .client
+            .delete(&self.session_uri)
+            .headers(self.client.get_headers().await?)
+            .header(CONTENT_LENGTH, 0)
+            .send()
+            .await?;
+        // Google responds with the non-standard status 499 when the session is cancelled.
+        if response.status().is_success() || response.status().as_u16() == 499 {
+            Ok(())
+        } else {
+            Err(Error::from_response(response).await)
+        }
+    }
+
+    // Sends `chunk`, which starts at `offset`, until it is committed completely, resending the
+    // part that was not committed after a partial commit or an interruption. Interruptions are
+    // resumed as often as the retry policy of the client allows.
+    async fn send_resuming(
+        &self,
+        offset: u64,
+        chunk: &[u8],
+        total: Option<u64>,
+        hashes: Option<&Hashes>,
+    ) -> crate::Result<UploadStatus> {
+        let end = offset + chunk.len() as u64;
+        let mut committed = offset;
+        let mut failures = 0;
+        let mut started = std::time::Instant::now();
+        // after an interruption, the committed offset is queried before the chunk is resent
+        let mut interrupted = false;
+        loop {
+            let result = if interrupted {
+                self.status().await
+            } else {
+                let start = (committed - offset) as usize;
+                self.put_chunk(committed, chunk[start..].to_vec(), total, hashes)
+                    .await
+            };
+            let status = match result {
+                Ok(status) => status,
+                Err(e) if e.retryable() => {
+                    failures += 1;
+                    let policy = &self.client.retry_policy;
+                    match policy.backoff(failures, started.elapsed()) {
+                        Some(backoff) => tokio::time::delay_for(backoff).await,
+                        None => return Err(e),
+                    }
+                    interrupted = true;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            interrupted = false;
+            match status {
+                UploadStatus::Complete(object) => return Ok(UploadStatus::Complete(object)),
+                UploadStatus::InProgress(n) if n < offset || n > end => {
+                    let msg = format!(
+                        "server committed {} bytes, expected between {} and {}",
+                        n, offset, end
+                    );
+                    return Err(Error::Other(msg));
+                }
+                UploadStatus::InProgress(n) if n == end => return Ok(UploadStatus::InProgress(n)),
+                UploadStatus::InProgress(n) => {
+                    if n > committed {
+                        failures = 0;
+                        started = std::time::Instant::now();
+                    }
+                    committed = n;
+                }
+            }
+        }
+    }
+
+    async fn parse_status(response: reqwest::Response) -> crate::Result<UploadStatus> {
+        match response.status() {
+            StatusCode::OK | StatusCode::CREATED => {
+                Ok(UploadStatus::Complete(Box::new(response.json().await?)))
+            }
+            StatusCode::PERMANENT_REDIRECT => {
+                // The Range header looks like `bytes=0-42`, and is absent if nothing has been
+                // committed yet.
+                let committed = match response.headers().get(RANGE) {
+                    Some(range) => range
+                        .to_str()
+                        .ok()
+                        .and_then(|r| r.rsplit('-').next())
+                        .and_then(|end| end.parse::<u64>().ok())
+                        .map(|end| end + 1)
+                        .ok_or_else(|| Error::new("invalid Range header in upload response"))?,
+                    None => 0,
+                };
+                Ok(UploadStatus::InProgress(committed))
+            }
+            _ => Err(Error::from_response(response).await),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{object_json, Request, Response, TestServer};
+    use std::sync::{Arc, Mutex};
+
+    // Emulates the upload endpoint. The first POST starts a session, every PUT appends its body to
+    // `stored`, committing at most `max_commit` bytes per request.
+    fn upload_handler(
+        stored: Arc<Mutex<Vec<u8>>>,
+        max_commit: usize,
+    ) -> impl Fn(&Request) -> Response + Send + Sync + 'static {
+        move |request| {
+            if request.method == "POST" {
+                let location = format!("http://{}/session/1", request.header("Host").unwrap());
+                return Response::new(200).header("Location", &location);
+            }
+            let mut stored = stored.lock().unwrap();
+            let range = request.header("Content-Range").unwrap();
+            let range = range.trim_start_matches("bytes ");
+            let (span, total) = range.split_at(range.find('/').unwrap());
+            if span != "*" {
+                let start: usize = span.split('-').next().unwrap().parse().unwrap();
+                assert_eq!(
+                    start,
+                    stored.len(),
+                    "chunk does not start at committed offset"
+                );
+                let commit = request.body.len().min(max_commit);
+                stored.extend_from_slice(&request.body[..commit]);
+            }
+            match total[1..].parse::<usize>() {
+                Ok(total) if total == stored.len() => {
+                    Response::new(200).json(object_json("bucket", "file", &stored))
+                }
+                _ if stored.is_empty() => Response::new(308),
+                _ => Response::new(308).header("Range", &format!("bytes=0-{}", stored.len() - 1)),
+            }
+        }
+    }
+
+    fn data(len: usize) -> Vec<u8> {
+        (0..len).map(|i| i as u8).collect()
+    }
+
+    fn pieces(data: &[u8]) -> impl futures::Stream<Item = crate::Result<Vec<u8>>> {
+        let pieces: Vec<_> = data.chunks(10_000).map(|c| Ok(c.to_vec())).collect();
+        futures::stream::iter(pieces)
+    }
+
+    #[tokio::test]
+    async fn upload_in_chunks() -> Result<(), Box<dyn std::error::Error>> {
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let server = TestServer::start(upload_handler(stored.clone(), usize::MAX));
+        let client = server.client();
+        let data = data(2 * CHUNK_SIZE_MULTIPLE + 1000);
+
+        let upload = client
+            .object()
+            .start_resumable_upload("bucket", "file", "text/plain", None)
+            .await?
+            .with_chunk_size(1);
+        assert!(upload.session_uri().ends_with("/session/1"));
+        let object = upload.upload(0, pieces(&data), None).await?;
+        assert_eq!(object.size, data.len() as u64);
+        assert_eq!(*stored.lock().unwrap(), data);
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].path,
+            "/upload/storage/v1/b/bucket/o?uploadType=resumable&name=file"
+        );
+        assert_eq!(
+            requests[0].header("X-Upload-Content-Type"),
+            Some("text/plain")
+        );
+        let ranges: Vec<_> = requests[1..]
+            .iter()
+            .map(|r| r.header("Content-Range").unwrap())
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                "bytes 0-262143/*",
+                "bytes 262144-524287/*",
+                "bytes 524288-525287/525288",
+            ]
+        );
+        assert!(requests[1].header("x-goog-hash").is_none());
+        let hashes = Hashes::compute(Checksum::Crc32c, &data)?.unwrap();
+        assert_eq!(
+            requests[3].header("x-goog-hash"),
+            Some(hashes.header_value().as_str())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_streamed_with_length() -> Result<(), Box<dyn std::error::Error>> {
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let server = TestServer::start(upload_handler(stored.clone(), usize::MAX));
+        let client = server.client();
+        let data = data(12345);
+
+        let object = client
+            .object()
+            .create_streamed("bucket", pieces(&data), 12345, "file", "text/plain")
+            .await?;
+        assert_eq!(object.size, 12345);
+        assert_eq!(*stored.lock().unwrap(), data);
+        let requests = server.requests();
+        assert_eq!(requests[0].header("X-Upload-Content-Length"), Some("12345"));
+        assert_eq!(
+            requests[1].header("Content-Range"),
+            Some("bytes 0-12344/12345")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn partially_committed_chunk_is_resent() -> Result<(), Box<dyn std::error::Error>> {
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let server = TestServer::start(upload_handler(stored.clone(), CHUNK_SIZE_MULTIPLE));
+        let client = server.client();
+        let data = data(2 * CHUNK_SIZE_MULTIPLE + 7);
+
+        let object = client
+            .object()
+            .start_resumable_upload("bucket", "file", "text/plain", None)
+            .await?
+            .with_chunk_size(2 * CHUNK_SIZE_MULTIPLE)
+            .upload(0, pieces(&data), None)
+            .await?;
+        assert_eq!(object.size, data.len() as u64);
+        assert_eq!(*stored.lock().unwrap(), data);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn interrupted_chunk_is_resumed() -> Result<(), Box<dyn std::error::Error>> {
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let handler = upload_handler(stored.clone(), usize::MAX);
+        let failed = Mutex::new(false);
+        let server = TestServer::start(move |request| {
+            let mut failed = failed.lock().unwrap();
+            if request.method == "PUT" && !request.body.is_empty() && !*failed {
+                *failed = true;
+                return Response::google_error(503, "backendError", "Backend Error");
+            }
+            handler(request)
+        });
+        let client = server.client();
+        let data = data(1000);
+
+        let object = client
+            .object()
+            .create_streamed("bucket", pieces(&data), None, "file", "text/plain")
+            .await?;
+        assert_eq!(object.size, 1000);
+        assert_eq!(*stored.lock().unwrap(), data);
+        let ranges: Vec<_> = server.requests()[1..]
+            .iter()
+            .map(|r| r.header("Content-Range").unwrap().to_string())
+            .collect();
+        assert_eq!(
+            ranges,
+            vec!["bytes 0-999/1000", "bytes */*", "bytes 0-999/1000"]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn plain_text_503_is_resumed() -> Result<(), Box<dyn std::error::Error>> {
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let handler = upload_handler(stored.clone(), usize::MAX);
+        let failures = Mutex::new(0);
+        let server = TestServer::start(move |request| {
+            let mut failures = failures.lock().unwrap();
+            // fail the first chunk and the first status query that follows it
+            if request.method == "PUT" && *failures < 2 {
+                let status_query = request.header("Content-Range") == Some("bytes */*");
+                if (*failures == 0 && !request.body.is_empty()) || (*failures == 1 && status_query)
+                {
+                    *failures += 1;
+                    return Response::new(503).body("Service Unavailable");
+                }
+            }
+            handler(request)
+        });
+        let client = server.client();
+        let data = data(1000);
+
+        let object = client
+            .object()
+            .create_streamed("bucket", pieces(&data), None, "file", "text/plain")
+            .await?;
+        assert_eq!(object.size, 1000);
+        assert_eq!(*stored.lock().unwrap(), data);
+        let ranges: Vec<_> = server.requests()[1..]
+            .iter()
+            .map(|r| r.header("Content-Range").unwrap().to_string())
+            .collect();
+        assert_eq!(
+            ranges,
+            vec![
+                "bytes 0-999/1000",
+                "bytes */*",
+                "bytes */*",
+                "bytes 0-999/1000"
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resume_from_session_uri() -> Result<(), Box<dyn std::error::Error>> {
+        let stored = Arc::new(Mutex::new(Vec::new()));
+        let server = TestServer::start(upload_handler(stored.clone(), usize::MAX));
+        let client = server.client();
+        let data = data(CHUNK_SIZE_MULTIPLE + 10);
+
+        let upload = client
+            .object()
+            .start_resumable_upload("bucket", "file", "text/plain", None)
+            .await?;
+        let status = upload
+            .upload_chunk(0, data[..CHUNK_SIZE_MULTIPLE].to_vec(), None)
+            .await?;
+        assert!(matches!(status, UploadStatus::InProgress(n) if n == CHUNK_SIZE_MULTIPLE as u64));
+
+        let upload = client.object().resume_upload(upload.session_uri());
+        let offset = match upload.status().await? {
+            UploadStatus::InProgress(offset) => offset,
+            UploadStatus::Complete(_) => panic!("upload should not be complete"),
+        };
+        let rest = pieces(&data[offset as usize..]);
+        let object = upload.upload(offset, rest, Some(data.len() as u64)).await?;
+        assert_eq!(object.size, data.len() as u64);
+        assert_eq!(*stored.lock().unwrap(), data);
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+use reqwest::header::{HeaderMap, HeaderName};
+
+/// A [customer-supplied encryption key](https://cloud.google.com/storage/docs/encryption/customer-supplied-keys),
+/// an AES-256 key with which Cloud Storage encrypts an object. Google does not store the key, so
+/// the same key must be sent with every request that reads the contents of the object.
+///
+/// ### Example
+/// ```rust
+/// use cloud_storage::EncryptionKey;
+///
+/// let key = EncryptionKey::from_base64("X4Y1c9uhvAAtrN2KbNv4cC9tIX09iebVbLIvtE1YRcs=")?;
+/// assert_eq!(key.key_sha256(), "kosxoQFIDdeU/sd0g5ZSs4dZW9oOgSWPjygwzxcdNqw=");
+/// # Ok::<(), cloud_storage::Error>(())
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey {
+    key: String,
+    key_sha256: String,
+}
+
+impl EncryptionKey {
+    /// Create a new instance of `EncryptionKey` from the 32 bytes of an AES-256 key.
+    pub fn new(key: [u8; 32]) -> Self {
+        Self {
+            key: base64::encode(key),
+            key_sha256: base64::encode(openssl::sha::sha256(&key)),
+        }
+    }
+
+    /// Create a new instance of `EncryptionKey` from the base64 encoding of an AES-256 key, which
+    /// is how `gsutil` and the Cloud Console accept keys.
+    pub fn from_base64(key: &str) -> crate::Result<Self> {
+        let bytes = base64::decode(key)
+            .map_err(|e| crate::Error::Other(format!("invalid encryption key: {}", e)))?;
+        if bytes.len() != 32 {
+            return Err(crate::Error::Other(format!(
+                "invalid encryption key: expected 32 bytes, found {}",
+                bytes.len()
+            )));
+        }
+        let mut key = [0; 32];
+        key.copy_from_slice(&bytes);
+        Ok(Self::new(key))
+    }
+
+    /// The base64 encoded SHA-256 hash of the key, which identifies the key without revealing
+    /// it. It is reported as the `key_sha256` of the `customer_encryption` of objects that are
+    /// encrypted with this key.
+    pub fn key_sha256(&self) -> &str {
+        &self.key_sha256
+    }
+
+    /// Adds the headers that encrypt or decrypt the object of a request with this key.
+    pub(crate) fn add_headers(&self, headers: &mut HeaderMap) -> crate::Result<()> {
+        self.insert(
+            headers,
+            [
+                "x-goog-encryption-algorithm",
+                "x-goog-encryption-key",
+                "x-goog-encryption-key-sha256",
+            ],
+        )
+    }
+
+    /// Adds the headers that decrypt the source object of a copy or rewrite with this key.
+    pub(crate) fn add_source_headers(&self, headers: &mut HeaderMap) -> crate::Result<()> {
+        self.insert(
+            headers,
+            [
+                "x-goog-copy-source-encryption-algorithm",
+                "x-goog-copy-source-encryption-key",
+                "x-goog-copy-source-encryption-key-sha256",
+            ],
+        )
+    }
+
+    fn insert(&self, headers: &mut HeaderMap, names: [&'static str; 3]) -> crate::Result<()> {
+        let [algorithm, key, key_sha256] = names;
+        headers.insert(HeaderName::from_static(algorithm), "AES256".parse()?);
+        headers.insert(HeaderName::from_static(key), self.key.parse()?);
+        headers.insert(
+            HeaderName::from_static(key_sha256),
+            self.key_sha256.parse()?,
+        );
+        Ok(())
+    }
+}
+
+// The key itself is secret, so only its hash is printed.
+impl std::fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("EncryptionKey")
+            .field("key_sha256", &self.key_sha256)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_base64() -> Result<(), Box<dyn std::error::Error>> {
+        let key = EncryptionKey::from_base64("AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=")?;
+        assert_eq!(key, EncryptionKey::new([0; 32]));
+        assert_eq!(
+            key.key_sha256(),
+            "Zmh6rfhivXdsj8GLjp+OIAiXFIVu4jOzkCpZHQ1fKSU="
+        );
+        assert!(!format!("{:?}", key).contains("AAAA"));
+
+        assert!(EncryptionKey::from_base64("c2hvcnQ=").is_err());
+        assert!(EncryptionKey::from_base64("not base64!").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn headers() -> Result<(), Box<dyn std::error::Error>> {
+        let key = EncryptionKey::new([0; 32]);
+        let mut headers = HeaderMap::new();
+        key.add_headers(&mut headers)?;
+        key.add_source_headers(&mut headers)?;
+        assert_eq!(headers["x-goog-encryption-algorithm"], "AES256");
+        assert_eq!(
+            headers["x-goog-encryption-key"],
+            "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA="
+        );
+        assert_eq!(
+            headers["x-goog-copy-source-encryption-key-sha256"],
+            "Zmh6rfhivXdsj8GLjp+OIAiXFIVu4jOzkCpZHQ1fKSU="
+        );
+        Ok(())
+    }
+}
@@ -11,6 +11,18 @@ pub enum Error {
     Jwt(jsonwebtoken::errors::Error),
     /// If we cannot deserialize one of the repsonses sent by Google, this variant is used.
     Serialization(serde_json::error::Error),
+    /// If the checksum of uploaded or downloaded data does not match the checksum reported by
+    /// Google, the data was corrupted in transit and this variant is returned.
+    ChecksumMismatch {
+        /// The checksum algorithm that detected the mismatch, either `crc32c` or `md5`.
+        algorithm: &'static str,
+        /// The base64 encoded checksum reported by Google.
+        expected: String,
+        /// The base64 encoded checksum of the data that was sent or received.
+        actual: String,
+    },
+    /// If credentials are missing, cannot be read or are malformed, this variant is used.
+    Credentials(String),
     /// If another failure causes the error, this variant is populated.
     Other(String),
 }
@@ -19,6 +31,73 @@ impl Error {
     pub(crate) fn new(msg: &str) -> Error {
         Error::Other(msg.to_string())
     }
+
+    /// Turns an unsuccessful response into an error. Media requests, upload sessions and
+    /// emulators do not always respond with a JSON error body, in which case the error is derived
+    /// from the status code, so that transient failures remain retryable.
+    pub(crate) async fn from_response(response: reqwest::Response) -> Error {
+        let status = response.status();
+        let text = match response.text().await {
+            Ok(text) => text,
+            Err(e) => return e.into(),
+        };
+        if let Ok(e) = serde_json::from_str::<GoogleErrorResponse>(&text) {
+            return Error::Google(e);
+        }
+        match GoogleErrorResponse::from_status(status.as_u16(), text.clone()) {
+            Some(e) => Error::Google(e),
+            None => Error::Other(format!("{}: {}", status, text)),
+        }
+    }
+
+    /// Whether the request failed because its `Preconditions` did not hold, for example because
+    /// another writer modified the object first.
+    pub fn is_condition_not_met(&self) -> bool {
+        match self {
+            Self::Google(e) => e.code() == 412 || e.errors_has_reason(&Reason::ConditionNotMet),
+            _ => false,
+        }
+    }
+
+    /// Whether the error is transient, so that repeating the request that caused it may succeed.
+    /// This is the case for network failures such as timeouts and connection resets, for
+    /// `408 Request Timeout`, `429 Too Many Requests` and `5xx` responses, for rate limit errors
+    /// and for data that was corrupted in transit.
+    ///
+    /// Note that repeating a request that modifies an object is only safe if it is idempotent,
+    /// see `RetryPolicy`.
+    pub fn retryable(&self) -> bool {
+        match self {
+            Self::Google(e) => {
+                is_retryable_status(e.code())
+                    || [
+                        Reason::RateLimitExceeded,
+                        Reason::UserRateLimitExceeded,
+                        Reason::UsageLimitsRateLimitExceeded,
+                        Reason::UploadBrokenConnection,
+                        Reason::BackendError,
+                        Reason::InternalError,
+                        Reason::GatewayTimeout,
+                    ]
+                    .iter()
+                    .any(|reason| e.errors_has_reason(reason))
+            }
+            Self::Reqwest(e) => {
+                e.is_timeout()
+                    || e.is_connect()
+                    || e.is_request()
+                    || e.is_body()
+                    || matches!(e.status(), Some(s) if is_retryable_status(s.as_u16()))
+            }
+            Self::ChecksumMismatch { .. } => true,
+            _ => false,
+        }
+    }
+}
+
+/// Whether a response with this status code is worth retrying.
+pub(crate) fn is_retryable_status(code: u16) -> bool {
+    code == 408 || code == 429 || (500..600).contains(&code)
 }
 
 impl std::fmt::Display for Error {
@@ -35,6 +114,8 @@ impl std::error::Error for Error {
             Self::Ssl(e) => Some(e),
             Self::Jwt(e) => Some(e),
             Self::Serialization(e) => Some(e),
+            Self::ChecksumMismatch { .. } => None,
+            Self::Credentials(_) => None,
             Self::Other(_) => None,
         }
     }
@@ -109,11 +190,53 @@ pub struct GoogleErrorResponse {
 }
 
 impl GoogleErrorResponse {
+    /// Builds an error response from the status code of a response whose body is not JSON, as is
+    /// the case for some media downloads. Returns `None` for status codes that do not correspond
+    /// to a known `Reason`.
+    pub(crate) fn from_status(code: u16, message: String) -> Option<Self> {
+        let reason = match code {
+            401 => Reason::AuthError,
+            403 => Reason::Forbidden,
+            404 => Reason::NotFound,
+            410 => Reason::Gone,
+            412 => Reason::ConditionNotMet,
+            416 => Reason::RequestedRangeNotSatisfiable,
+            429 => Reason::RateLimitExceeded,
+            500 => Reason::InternalError,
+            502 | 503 => Reason::BackendError,
+            504 => Reason::GatewayTimeout,
+            _ => return None,
+        };
+        Some(Self {
+            error: ErrorList {
+                errors: vec![GoogleError {
+                    domain: "global".to_string(),
+                    reason,
+                    message: message.clone(),
+                    location_type: None,
+                    location: None,
+                }],
+                code,
+                message,
+            },
+        })
+    }
+
     /// Return list of errors returned by Google
     pub fn errors(&self) -> &[GoogleError] {
         &self.error.errors
     }
 
+    /// Return the HTTP status code of the response
+    pub fn code(&self) -> u16 {
+        self.error.code
+    }
+
+    /// Return the message that summarizes the errors
+    pub fn message(&self) -> &str {
+        &self.error.message
+    }
+
     /// Check whether errors contain given reason
     pub fn errors_has_reason(&self, reason: &Reason) -> bool {
         self.errors()
@@ -144,7 +267,7 @@ struct ErrorList {
 
 /// Google Error structure
 #[derive(Debug, serde::Deserialize)]
-#[serde(rename = "camelCase")]
+#[serde(rename_all = "camelCase")]
 pub struct GoogleError {
     domain: String,
     reason: Reason,
@@ -158,6 +281,26 @@ impl GoogleError {
     pub fn is_reason(&self, reason: &Reason) -> bool {
         &self.reason == reason
     }
+
+    /// The scope of the error, for example `global`.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// A human readable description of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// How `location` should be interpreted, for example `header` or `parameter`.
+    pub fn location_type(&self) -> Option<&str> {
+        self.location_type.as_deref()
+    }
+
+    /// The part of the request that caused the error, for example the name of a parameter.
+    pub fn location(&self) -> Option<&str> {
+        self.location.as_deref()
+    }
 }
 
 impl From<GoogleErrorResponse> for Error {
@@ -387,23 +530,3 @@ pub enum Reason {
     // NONEXHAUST
     GatewayTimeout,
 }
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename = "camelCase")]
-enum BadRequest {}
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename = "camelCase")]
-enum Unauthorized {}
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename = "camelCase")]
-enum Forbidden {}
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename = "camelCase")]
-enum PreconditionFailed {}
-
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename = "camelCase")]
-enum InternalServerError {}
@@ -83,57 +83,59 @@
 //! ```
 #![forbid(unsafe_code, missing_docs)]
 
+/// Credentials that authenticate the requests of a `Client`.
+pub mod auth;
+mod byte_range;
+mod checksum;
+/// Clients for Google Cloud Storage endpoints.
+pub mod client;
 mod download_options;
+mod encryption_key;
 mod error;
+mod post_policy;
+mod preconditions;
 /// Contains objects as represented by Google, to be used for serialization and deserialization.
 mod resources;
+mod retry;
+mod signed_url_options;
+#[cfg(test)]
+mod test_server;
 mod token;
+mod url_signer;
 
+pub use crate::auth::TokenProvider;
+pub use crate::byte_range::{ByteRange, ContentRange};
+pub use crate::checksum::Checksum;
+pub use crate::client::Client;
 pub use crate::error::*;
+pub use crate::post_policy::{PostPolicy, SignedPostPolicy};
+pub use crate::preconditions::Preconditions;
 use crate::resources::service_account::ServiceAccount;
 pub use crate::resources::{
     bucket::{Bucket, NewBucket},
     object::Object,
     *,
 };
-use crate::token::Token;
+pub use crate::retry::RetryPolicy;
 pub use download_options::DownloadOptions;
-use tokio::sync::Mutex;
+pub use encryption_key::EncryptionKey;
+pub use signed_url_options::SignedUrlOptions;
+pub use url_signer::{UrlSigner, UrlStyle};
 
 lazy_static::lazy_static! {
-    /// Static `Token` struct that caches
-    static ref TOKEN_CACHE: Mutex<Token> = Mutex::new(Token::new(
-        "https://www.googleapis.com/auth/devstorage.full_control",
-    ));
-
-    static ref IAM_TOKEN_CACHE: Mutex<Token> = Mutex::new(Token::new(
-        "https://www.googleapis.com/auth/iam"
-    ));
-
     /// The struct is the parsed service account json file. It is publicly exported to enable easier
     /// debugging of which service account is currently used. It is of the type
-    /// [ServiceAccount](service_account/struct.ServiceAccount.html).
+    /// [ServiceAccount](service_account/struct.ServiceAccount.html). Dereferencing it panics when the
+    /// service account cannot be loaded, use `ServiceAccount::from_env` to handle that case.
     pub static ref SERVICE_ACCOUNT: ServiceAccount = ServiceAccount::get();
 
-    static ref CLIENT: reqwest::Client = reqwest::Client::new();
+    /// The default `Client` that is used by the free functions such as `Object::create`.
+    static ref CLOUD_CLIENT: Client = Client::default();
 }
 
 /// A type alias where the error is set to be `cloud_storage::Error`.
 pub type Result<T> = std::result::Result<T, crate::Error>;
 
-const BASE_URL: &str = "https://www.googleapis.com/storage/v1";
-
-async fn get_headers() -> Result<reqwest::header::HeaderMap> {
-    let mut result = reqwest::header::HeaderMap::new();
-    let mut guard = TOKEN_CACHE.lock().await;
-    let token = guard.get().await?;
-    result.insert(
-        reqwest::header::AUTHORIZATION,
-        format!("Bearer {}", token).parse().unwrap(),
-    );
-    Ok(result)
-}
-
 fn from_str<'de, T, D>(deserializer: D) -> std::result::Result<T, D::Error>
 where
     T: std::str::FromStr,
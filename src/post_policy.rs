@@ -0,0 +1,94 @@
+use std::collections::BTreeMap;
+
+/// A [policy document](https://cloud.google.com/storage/docs/authentication/signatures#policy-document)
+/// that lets browsers upload an object with an HTML form, without any authentication. Every
+/// field of the form must be allowed by a condition of the policy, and the policy is signed by
+/// `Object::post_policy`.
+/// ### Example
+/// ```rust
+/// use cloud_storage::PostPolicy;
+///
+/// let policy = PostPolicy::new("my_bucket", "uploads/${filename}")
+///     .key_starts_with("uploads/")
+///     .starts_with("content-type", "image/")
+///     .content_length_range(0, 10_000_000)
+///     .success_action_redirect("https://example.com/thanks");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PostPolicy {
+    pub(crate) bucket: String,
+    pub(crate) key: String,
+    pub(crate) key_prefix: Option<String>,
+    pub(crate) fields: BTreeMap<String, String>,
+    pub(crate) conditions: Vec<serde_json::Value>,
+}
+
+impl PostPolicy {
+    /// A policy that allows uploading the object `key` to `bucket`. `key` may contain
+    /// `${filename}`, which is replaced with the name of the file the user selected.
+    pub fn new(bucket: &str, key: &str) -> Self {
+        Self {
+            bucket: bucket.to_string(),
+            key: key.to_string(),
+            key_prefix: None,
+            fields: BTreeMap::new(),
+            conditions: Vec::new(),
+        }
+    }
+
+    /// Allows the form to upload any object whose name starts with `prefix`, instead of only
+    /// `key`.
+    pub fn key_starts_with(mut self, prefix: &str) -> Self {
+        self.key_prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Adds a hidden field to the form, and requires the form to send it with exactly this value.
+    /// Use this for fields such as `content-type`, `cache-control`, `acl` or `x-goog-meta-*`.
+    pub fn field(mut self, name: &str, value: &str) -> Self {
+        self.fields.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Requires the form to send this `content-type` field.
+    pub fn content_type(self, content_type: &str) -> Self {
+        self.field("content-type", content_type)
+    }
+
+    /// Redirects the browser to `url` after a successful upload.
+    pub fn success_action_redirect(self, url: &str) -> Self {
+        self.field("success_action_redirect", url)
+    }
+
+    /// Answers a successful upload with this status code, `200`, `201` or `204`, when no
+    /// `success_action_redirect` is set.
+    pub fn success_action_status(self, status: u16) -> Self {
+        self.field("success_action_status", &status.to_string())
+    }
+
+    /// Allows the form to send any value for the field `name` that starts with `prefix`, for
+    /// example a `content-type` that starts with `image/`. An empty prefix allows any value.
+    pub fn starts_with(mut self, name: &str, prefix: &str) -> Self {
+        let name = format!("${}", name);
+        self.conditions
+            .push(serde_json::json!(["starts-with", name, prefix]));
+        self
+    }
+
+    /// Rejects uploads whose size in bytes is not between `min` and `max`, inclusive.
+    pub fn content_length_range(mut self, min: u64, max: u64) -> Self {
+        self.conditions
+            .push(serde_json::json!(["content-length-range", min, max]));
+        self
+    }
+}
+
+/// A signed `PostPolicy`, which contains everything that is needed to build the HTML form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedPostPolicy {
+    /// The url to which the form is posted.
+    pub url: String,
+    /// The fields of the form, which are sent as hidden inputs. The file itself must be sent as
+    /// the last field of the form, named `file`.
+    pub fields: BTreeMap<String, String>,
+}
@@ -0,0 +1,111 @@
+/// [Preconditions](https://cloud.google.com/storage/docs/request-preconditions) on the generation
+/// and metageneration of an object, which make a request fail with `Reason::ConditionNotMet`
+/// instead of modifying the object when they do not hold. This can be used to implement
+/// compare-and-swap on objects, or to only create an object if it does not exist yet. Use
+/// `Error::is_condition_not_met` to detect that a precondition failed.
+///
+/// ### Example
+/// ```rust
+/// use cloud_storage::Preconditions;
+///
+/// // only succeeds if the object has not been modified since it was read at metageneration 3
+/// let unchanged = Preconditions::new().if_metageneration_match(3);
+/// // only succeeds if no live object exists with the same name
+/// let absent = Preconditions::does_not_exist();
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Preconditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) if_generation_match: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) if_generation_not_match: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) if_metageneration_match: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) if_metageneration_not_match: Option<i64>,
+}
+
+impl Preconditions {
+    /// Create a new instance of `Preconditions` without any conditions. Equivalent to
+    /// `Preconditions::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Preconditions that only hold if there is no live version of the object, which makes a
+    /// create fail instead of overwriting an existing object.
+    pub fn does_not_exist() -> Self {
+        Self::new().if_generation_match(0)
+    }
+
+    /// Only perform the request if the generation of the object matches `generation`. A
+    /// generation of `0` matches only if there is no live version of the object.
+    pub fn if_generation_match(mut self, generation: i64) -> Self {
+        self.if_generation_match = Some(generation);
+        self
+    }
+
+    /// Only perform the request if the generation of the object does not match `generation`. A
+    /// generation of `0` matches only if there is a live version of the object.
+    pub fn if_generation_not_match(mut self, generation: i64) -> Self {
+        self.if_generation_not_match = Some(generation);
+        self
+    }
+
+    /// Only perform the request if the metageneration of the object matches `metageneration`.
+    pub fn if_metageneration_match(mut self, metageneration: i64) -> Self {
+        self.if_metageneration_match = Some(metageneration);
+        self
+    }
+
+    /// Only perform the request if the metageneration of the object does not match
+    /// `metageneration`.
+    pub fn if_metageneration_not_match(mut self, metageneration: i64) -> Self {
+        self.if_metageneration_not_match = Some(metageneration);
+        self
+    }
+
+    /// Whether no conditions are set.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the generation of the object is pinned, which makes creating, deleting, composing,
+    /// copying and rewriting the object idempotent.
+    pub(crate) fn pins_generation(&self) -> bool {
+        self.if_generation_match.is_some()
+    }
+
+    /// Whether the metageneration of the object is pinned, which makes updating the object
+    /// idempotent.
+    pub(crate) fn pins_metageneration(&self) -> bool {
+        self.if_metageneration_match.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_parameters() -> Result<(), Box<dyn std::error::Error>> {
+        let url = reqwest::Url::parse("https://example.com/o")?;
+        let request = reqwest::Client::new()
+            .get(url.clone())
+            .query(&Preconditions::does_not_exist().if_metageneration_not_match(2))
+            .build()?;
+        assert_eq!(
+            request.url().query(),
+            Some("ifGenerationMatch=0&ifMetagenerationNotMatch=2")
+        );
+
+        let request = reqwest::Client::new()
+            .get(url)
+            .query(&Preconditions::new())
+            .build()?;
+        assert_eq!(request.url().query(), None);
+        assert!(Preconditions::new().is_empty());
+        Ok(())
+    }
+}
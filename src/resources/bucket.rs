@@ -1,7 +1,6 @@
-use crate::error::{Error, GoogleResponse};
+use crate::error::Error;
 use crate::resources::bucket_access_control::{BucketAccessControl, NewBucketAccessControl};
-pub use crate::resources::common::Entity;
-use crate::resources::common::ListResponse;
+pub use crate::resources::common::{Entity, Projection};
 use crate::resources::default_object_access_control::{
     DefaultObjectAccessControl, NewDefaultObjectAccessControl,
 };
@@ -325,7 +324,7 @@ pub struct Billing {
 }
 
 /// The type of storage that is used. Pertains to availability, performance and cost.
-#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum StorageClass {
     /// Standard Storage is best for data that is frequently accessed ("hot" data) and/or stored for
@@ -539,6 +538,63 @@ pub struct TestIamPermission {
     permissions: Vec<String>,
 }
 
+/// A set of parameters that select the buckets that are listed by `Bucket::list_with`.
+///
+/// ### Example
+/// ```rust
+/// use cloud_storage::bucket::{ListRequest, Projection};
+///
+/// let request = ListRequest::new().prefix("logs-").projection(Projection::Full);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListRequest {
+    pub(crate) prefix: Option<String>,
+    pub(crate) max_results: Option<u32>,
+    pub(crate) projection: Option<Projection>,
+}
+
+impl ListRequest {
+    /// Create a new instance of `ListRequest`, which lists all buckets of the project. Equivalent
+    /// to `ListRequest::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lists the buckets whose names start with `prefix`.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// The maximum number of buckets on each page. Google returns at most 1000 per page, which
+    /// is also the default.
+    pub fn max_results(mut self, max_results: u32) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Which properties of the buckets are returned. Defaults to `Projection::NoAcl`.
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    // The query parameters of the request, without the project.
+    pub(crate) fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = Vec::new();
+        if let Some(ref prefix) = self.prefix {
+            query.push(("prefix", prefix.clone()));
+        }
+        if let Some(max_results) = self.max_results {
+            query.push(("maxResults", max_results.to_string()));
+        }
+        if let Some(projection) = self.projection {
+            query.push(("projection", projection.as_str().to_string()));
+        }
+        query
+    }
+}
+
 impl Bucket {
     /// Creates a new `Bucket`. There are many options that you can provide for creating a new
     /// bucket, so the `NewBucket` resource contains all of them. Note that `NewBucket` implements
@@ -562,22 +618,7 @@ impl Bucket {
     /// # }
     /// ```
     pub async fn create(new_bucket: &NewBucket) -> crate::Result<Self> {
-        let url = format!("{}/b/", crate::BASE_URL);
-        let project = &crate::SERVICE_ACCOUNT.project_id;
-        let query = [("project", project)];
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .post(&url)
-            .headers(crate::get_headers().await?)
-            .query(&query)
-            .json(new_bucket)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.bucket().create(new_bucket).await
     }
 
     /// The synchronous equivalent of `Bucket::create`.
@@ -606,21 +647,7 @@ impl Bucket {
     /// # }
     /// ```
     pub async fn list() -> Result<Vec<Self>, Error> {
-        let url = format!("{}/b/", crate::BASE_URL);
-        let project = &crate::SERVICE_ACCOUNT.project_id;
-        let query = [("project", project)];
-        let result: GoogleResponse<ListResponse<Self>> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .query(&query)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s.items),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.bucket().list().await
     }
 
     /// The synchronous equivalent of `Bucket::list`.
@@ -633,6 +660,38 @@ impl Bucket {
         Self::list().await
     }
 
+    /// Lists the `Bucket`s within this project that are selected by `request`, one page at a
+    /// time.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::bucket::{Bucket, ListRequest};
+    /// use futures::TryStreamExt;
+    ///
+    /// let request = ListRequest::new().prefix("logs-").max_results(100);
+    /// let buckets: Vec<Bucket> = Bucket::list_with(request).await?.try_concat().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_with(
+        request: ListRequest,
+    ) -> Result<impl futures::Stream<Item = Result<Vec<Self>, Error>>, Error> {
+        crate::CLOUD_CLIENT.bucket().list_with(request).await
+    }
+
+    /// The synchronous equivalent of `Bucket::list_with`, which collects all pages.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn list_with_sync(request: ListRequest) -> Result<Vec<Self>, Error> {
+        use futures::TryStreamExt;
+
+        Self::list_with(request).await?.try_concat().await
+    }
+
     /// Returns a single `Bucket` by its name. If the Bucket does not exist, an error is returned.
     /// ### Example
     /// ```
@@ -652,18 +711,7 @@ impl Bucket {
     /// # }
     /// ```
     pub async fn read(name: &str) -> crate::Result<Self> {
-        let url = format!("{}/b/{}", crate::BASE_URL, name);
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.bucket().read(name).await
     }
 
     /// The synchronous equivalent of `Bucket::read`.
@@ -702,19 +750,7 @@ impl Bucket {
     /// # }
     /// ```
     pub async fn update(&self) -> crate::Result<Self> {
-        let url = format!("{}/b/{}", crate::BASE_URL, self.name);
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .put(&url)
-            .headers(crate::get_headers().await?)
-            .json(self)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.bucket().update(self).await
     }
 
     /// The synchronous equivalent of `Bucket::update`.
@@ -748,17 +784,7 @@ impl Bucket {
     /// # }
     /// ```
     pub async fn delete(self) -> crate::Result<()> {
-        let url = format!("{}/b/{}", crate::BASE_URL, self.name);
-        let response = crate::CLIENT
-            .delete(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::Google(response.json().await?))
-        }
+        crate::CLOUD_CLIENT.bucket().delete(self).await
     }
 
     /// The synchronous equivalent of `Bucket::delete`.
@@ -791,18 +817,7 @@ impl Bucket {
     /// # }
     /// ```
     pub async fn get_iam_policy(&self) -> crate::Result<IamPolicy> {
-        let url = format!("{}/b/{}/iam", crate::BASE_URL, self.name);
-        let result: GoogleResponse<IamPolicy> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.bucket().get_iam_policy(self).await
     }
 
     /// The synchronous equivalent of `Bucket::get_iam_policy`.
@@ -847,19 +862,7 @@ impl Bucket {
     /// # }
     /// ```
     pub async fn set_iam_policy(&self, iam: &IamPolicy) -> crate::Result<IamPolicy> {
-        let url = format!("{}/b/{}/iam", crate::BASE_URL, self.name);
-        let result: GoogleResponse<IamPolicy> = crate::CLIENT
-            .put(&url)
-            .headers(crate::get_headers().await?)
-            .json(iam)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.bucket().set_iam_policy(self, iam).await
     }
 
     /// The synchronous equivalent of `Bucket::set_iam_policy`.
@@ -885,24 +888,10 @@ impl Bucket {
     /// # }
     /// ```
     pub async fn test_iam_permission(&self, permission: &str) -> crate::Result<TestIamPermission> {
-        if permission == "storage.buckets.list" || permission == "storage.buckets.create" {
-            return Err(Error::new(
-                "tested permission must not be `storage.buckets.list` or `storage.buckets.create`",
-            ));
-        }
-        let url = format!("{}/b/{}/iam/testPermissions", crate::BASE_URL, self.name);
-        let result: GoogleResponse<TestIamPermission> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .query(&[("permissions", permission)])
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .bucket()
+            .test_iam_permission(self, permission)
+            .await
     }
 
     /// The synchronous equivalent of `Bucket::test_iam_policy`.
@@ -1,5 +1,3 @@
-use crate::error::GoogleResponse;
-use crate::resources::common::ListResponse;
 pub use crate::resources::common::{Entity, ProjectTeam, Role};
 
 /// The BucketAccessControl resource represents the Access Control Lists (ACLs) for buckets within
@@ -14,11 +12,11 @@ pub use crate::resources::common::{Entity, ProjectTeam, Role};
 /// There are three roles that can be assigned to an entity:
 ///
 /// * READERs can get the bucket, though no acl property will be returned, and list the bucket's
-/// objects.
+///   objects.
 /// * WRITERs are READERs, and they can insert objects into the bucket and delete the bucket's
-/// objects.
+///   objects.
 /// * OWNERs are WRITERs, and they can get the acl property of a bucket, update a bucket, and call
-/// all BucketAccessControl methods on the bucket.
+///   all BucketAccessControl methods on the bucket.
 #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct BucketAccessControl {
@@ -47,7 +45,7 @@ pub struct BucketAccessControl {
     /// * The user liz@example.com would be user-liz@example.com.
     /// * The group example@googlegroups.com would be group-example@googlegroups.com.
     /// * To refer to all members of the G Suite for Business domain example.com, the entity would
-    /// be domain-example.com.
+    ///   be domain-example.com.
     pub entity: Entity,
     /// The access permission for the entity.
     pub role: Role,
@@ -83,7 +81,7 @@ pub struct NewBucketAccessControl {
     /// * The user liz@example.com would be user-liz@example.com.
     /// * The group example@googlegroups.com would be group-example@googlegroups.com.
     /// * To refer to all members of the G Suite for Business domain example.com, the entity would
-    /// be domain-example.com.
+    ///   be domain-example.com.
     pub entity: Entity,
     /// The access permission for the entity.
     pub role: Role,
@@ -116,19 +114,10 @@ impl BucketAccessControl {
         bucket: &str,
         new_bucket_access_control: &NewBucketAccessControl,
     ) -> crate::Result<Self> {
-        let url = format!("{}/b/{}/acl", crate::BASE_URL, bucket);
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .post(&url)
-            .headers(crate::get_headers().await?)
-            .json(new_bucket_access_control)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .bucket_access_control()
+            .create(bucket, new_bucket_access_control)
+            .await
     }
 
     /// The synchronous equivalent of `BucketAccessControl::create`.
@@ -161,18 +150,10 @@ impl BucketAccessControl {
     /// # }
     /// ```
     pub async fn list(bucket: &str) -> crate::Result<Vec<Self>> {
-        let url = format!("{}/b/{}/acl", crate::BASE_URL, bucket);
-        let result: GoogleResponse<ListResponse<Self>> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s.items),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .bucket_access_control()
+            .list(bucket)
+            .await
     }
 
     /// The synchronous equivalent of `BucketAccessControl::list`.
@@ -202,18 +183,10 @@ impl BucketAccessControl {
     /// # }
     /// ```
     pub async fn read(bucket: &str, entity: &Entity) -> crate::Result<Self> {
-        let url = format!("{}/b/{}/acl/{}", crate::BASE_URL, bucket, entity);
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .bucket_access_control()
+            .read(bucket, entity)
+            .await
     }
 
     /// The synchronous equivalent of `BucketAccessControl::read`.
@@ -245,19 +218,10 @@ impl BucketAccessControl {
     /// # }
     /// ```
     pub async fn update(&self) -> crate::Result<Self> {
-        let url = format!("{}/b/{}/acl/{}", crate::BASE_URL, self.bucket, self.entity);
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .put(&url)
-            .headers(crate::get_headers().await?)
-            .json(self)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .bucket_access_control()
+            .update(self)
+            .await
     }
 
     /// The synchronous equivalent of `BucketAccessControl::update`.
@@ -288,17 +252,10 @@ impl BucketAccessControl {
     /// # }
     /// ```
     pub async fn delete(self) -> crate::Result<()> {
-        let url = format!("{}/b/{}/acl/{}", crate::BASE_URL, self.bucket, self.entity);
-        let response = crate::CLIENT
-            .delete(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(crate::Error::Google(response.json().await?))
-        }
+        crate::CLOUD_CLIENT
+            .bucket_access_control()
+            .delete(self)
+            .await
     }
 
     /// The synchronous equivalent of `BucketAccessControl::delete`.
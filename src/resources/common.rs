@@ -1,6 +1,25 @@
 use serde::Serializer;
 use std::str::FromStr;
 
+/// Which properties of the objects or buckets are returned by a listing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Projection {
+    /// All properties.
+    Full,
+    /// All properties except the access control lists, `acl` and `defaultObjectAcl`. This is the
+    /// default.
+    NoAcl,
+}
+
+impl Projection {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Projection::Full => "full",
+            Projection::NoAcl => "noAcl",
+        }
+    }
+}
+
 /// Contains information about the team related to this `DefaultObjectAccessControls`
 #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -63,6 +82,8 @@ pub enum Role {
 pub(crate) struct ListResponse<T> {
     #[serde(default = "Vec::new")]
     pub items: Vec<T>,
+    #[serde(default = "Vec::new")]
+    pub prefixes: Vec<String>,
     pub next_page_token: Option<String>,
 }
 
@@ -1,7 +1,5 @@
 #![allow(unused_imports)]
 
-use crate::error::GoogleResponse;
-use crate::resources::common::ListResponse;
 pub use crate::resources::common::{Entity, ProjectTeam, Role};
 
 /// The DefaultObjectAccessControls resources represent the Access Control Lists (ACLs) applied to a
@@ -29,7 +27,7 @@ pub struct DefaultObjectAccessControl {
     /// * The user liz@example.com would be user-liz@example.com.
     /// * The group example@googlegroups.com would be group-example@googlegroups.com.
     /// * To refer to all members of the G Suite for Business domain example.com, the entity would
-    /// be domain-example.com.
+    ///   be domain-example.com.
     pub entity: Entity,
     /// The access permission for the entity.
     pub role: Role,
@@ -69,7 +67,7 @@ pub struct NewDefaultObjectAccessControl {
     /// * The user liz@example.com would be user-liz@example.com.
     /// * The group example@googlegroups.com would be group-example@googlegroups.com.
     /// * To refer to all members of the G Suite for Business domain example.com, the entity would
-    /// be domain-example.com.
+    ///   be domain-example.com.
     pub entity: Entity,
     /// The access permission for the entity.
     pub role: Role,
@@ -102,22 +100,10 @@ impl DefaultObjectAccessControl {
         bucket: &str,
         new_acl: &NewDefaultObjectAccessControl,
     ) -> crate::Result<Self> {
-        let url = format!("{}/b/{}/defaultObjectAcl", crate::BASE_URL, bucket);
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .post(&url)
-            .headers(crate::get_headers().await?)
-            .json(new_acl)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(mut s) => {
-                s.bucket = bucket.to_string();
-                Ok(s)
-            }
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .default_object_access_control()
+            .create(bucket, new_acl)
+            .await
     }
 
     /// The synchronous equivalent of `DefautObjectAccessControl::create`.
@@ -149,25 +135,10 @@ impl DefaultObjectAccessControl {
     /// # }
     /// ```
     pub async fn list(bucket: &str) -> crate::Result<Vec<Self>> {
-        let url = format!("{}/b/{}/defaultObjectAcl", crate::BASE_URL, bucket);
-        let result: GoogleResponse<ListResponse<Self>> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s
-                .items
-                .into_iter()
-                .map(|item| DefaultObjectAccessControl {
-                    bucket: bucket.to_string(),
-                    ..item
-                })
-                .collect()),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .default_object_access_control()
+            .list(bucket)
+            .await
     }
 
     /// The synchronous equivalent of `DefautObjectAccessControl::list`.
@@ -200,26 +171,10 @@ impl DefaultObjectAccessControl {
     /// # }
     /// ```
     pub async fn read(bucket: &str, entity: &Entity) -> crate::Result<Self> {
-        let url = format!(
-            "{}/b/{}/defaultObjectAcl/{}",
-            crate::BASE_URL,
-            bucket,
-            entity
-        );
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(mut s) => {
-                s.bucket = bucket.to_string();
-                Ok(s)
-            }
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .default_object_access_control()
+            .read(bucket, entity)
+            .await
     }
 
     /// The synchronous equivalent of `DefautObjectAccessControl::read`.
@@ -250,27 +205,10 @@ impl DefaultObjectAccessControl {
     /// # }
     /// ```
     pub async fn update(&self) -> crate::Result<Self> {
-        let url = format!(
-            "{}/b/{}/defaultObjectAcl/{}",
-            crate::BASE_URL,
-            self.bucket,
-            self.entity
-        );
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .put(&url)
-            .headers(crate::get_headers().await?)
-            .json(self)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(mut s) => {
-                s.bucket = self.bucket.to_string();
-                Ok(s)
-            }
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .default_object_access_control()
+            .update(self)
+            .await
     }
 
     /// The synchronous equivalent of `DefautObjectAccessControl::update`.
@@ -300,22 +238,10 @@ impl DefaultObjectAccessControl {
     /// # }
     /// ```
     pub async fn delete(self) -> Result<(), crate::Error> {
-        let url = format!(
-            "{}/b/{}/defaultObjectAcl/{}",
-            crate::BASE_URL,
-            self.bucket,
-            self.entity
-        );
-        let response = crate::CLIENT
-            .delete(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(crate::Error::Google(response.json().await?))
-        }
+        crate::CLOUD_CLIENT
+            .default_object_access_control()
+            .delete(self)
+            .await
     }
 
     /// The async equivalent of `DefautObjectAccessControl::delete`.
@@ -347,10 +273,6 @@ mod tests {
     #[tokio::test]
     async fn read() -> Result<(), Box<dyn std::error::Error>> {
         let bucket = crate::read_test_bucket().await;
-        NewDefaultObjectAccessControl {
-            entity: Entity::AllUsers,
-            role: Role::Reader,
-        };
         DefaultObjectAccessControl::read(&bucket.name, &Entity::AllUsers).await?;
         Ok(())
     }
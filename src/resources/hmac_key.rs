@@ -1,8 +1,6 @@
 #![allow(unused_imports)]
 #![allow(dead_code)]
 
-use crate::error::GoogleResponse;
-
 /// The `HmacKey` resource represents an HMAC key within Cloud Storage. The resource consists of a
 /// secret and `HmacMeta`. HMAC keys can be used as credentials for service accounts. For more
 /// information, see HMAC Keys.
@@ -58,9 +56,62 @@ pub enum HmacState {
     Deleted,
 }
 
-#[derive(Debug, serde::Deserialize)]
-struct ListResponse {
-    items: Vec<HmacMeta>,
+/// A set of parameters that select the HMAC keys that are listed by `HmacKey::list_with`.
+///
+/// ### Example
+/// ```rust
+/// use cloud_storage::hmac_key::ListRequest;
+///
+/// let request = ListRequest::new()
+///     .service_account_email("robot@my-project.iam.gserviceaccount.com")
+///     .show_deleted_keys();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListRequest {
+    pub(crate) service_account_email: Option<String>,
+    pub(crate) show_deleted_keys: bool,
+    pub(crate) max_results: Option<u32>,
+}
+
+impl ListRequest {
+    /// Create a new instance of `ListRequest`, which lists all keys of the project that are not
+    /// deleted. Equivalent to `ListRequest::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lists the keys of this service account.
+    pub fn service_account_email(mut self, service_account_email: &str) -> Self {
+        self.service_account_email = Some(service_account_email.to_string());
+        self
+    }
+
+    /// Also lists the keys that are in the `Deleted` state.
+    pub fn show_deleted_keys(mut self) -> Self {
+        self.show_deleted_keys = true;
+        self
+    }
+
+    /// The maximum number of keys on each page. Google returns at most 250 per page, which is
+    /// also the default.
+    pub fn max_results(mut self, max_results: u32) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    pub(crate) fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = Vec::new();
+        if let Some(ref service_account_email) = self.service_account_email {
+            query.push(("serviceAccountEmail", service_account_email.clone()));
+        }
+        if self.show_deleted_keys {
+            query.push(("showDeletedKeys", "true".to_string()));
+        }
+        if let Some(max_results) = self.max_results {
+            query.push(("maxResults", max_results.to_string()));
+        }
+        query
+    }
 }
 
 #[derive(serde::Serialize)]
@@ -70,8 +121,8 @@ struct UpdateRequest {
 }
 
 #[derive(serde::Serialize)]
-struct UpdateMeta {
-    state: HmacState,
+pub(crate) struct UpdateMeta {
+    pub(crate) state: HmacState,
 }
 
 impl HmacKey {
@@ -96,28 +147,7 @@ impl HmacKey {
     /// # }
     /// ```
     pub async fn create() -> crate::Result<Self> {
-        use reqwest::header::CONTENT_LENGTH;
-
-        let url = format!(
-            "{}/projects/{}/hmacKeys",
-            crate::BASE_URL,
-            crate::SERVICE_ACCOUNT.project_id
-        );
-        let query = [("serviceAccountEmail", &crate::SERVICE_ACCOUNT.client_email)];
-        let mut headers = crate::get_headers().await?;
-        headers.insert(CONTENT_LENGTH, 0.into());
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .post(&url)
-            .headers(headers)
-            .query(&query)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.hmac_key().create().await
     }
 
     /// The synchronous equivalent of `HmacKey::create`.
@@ -150,30 +180,7 @@ impl HmacKey {
     /// # }
     /// ```
     pub async fn list() -> crate::Result<Vec<HmacMeta>> {
-        let url = format!(
-            "{}/projects/{}/hmacKeys",
-            crate::BASE_URL,
-            crate::SERVICE_ACCOUNT.project_id
-        );
-        let response = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .text()
-            .await?;
-        let result: Result<GoogleResponse<ListResponse>, _> = serde_json::from_str(&response);
-
-        // This function rquires more complicated error handling because when there is only one
-        // entry, Google will return the response `{ "kind": "storage#hmacKeysMetadata" }` instead
-        // of a list with one element. This breaks the parser.
-        match result {
-            Ok(parsed) => match parsed {
-                GoogleResponse::Success(s) => Ok(s.items),
-                GoogleResponse::Error(e) => Err(e.into()),
-            },
-            Err(_) => Ok(vec![]),
-        }
+        crate::CLOUD_CLIENT.hmac_key().list().await
     }
 
     /// The async equivalent of `HmacKey::list`.
@@ -186,6 +193,37 @@ impl HmacKey {
         Self::list().await
     }
 
+    /// Lists the keys of the project that are selected by `request`, one page at a time.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::hmac_key::{HmacKey, HmacMeta, ListRequest};
+    /// use futures::TryStreamExt;
+    ///
+    /// let request = ListRequest::new().service_account_email("robot@my-project.iam.gserviceaccount.com");
+    /// let keys: Vec<HmacMeta> = HmacKey::list_with(request).await?.try_concat().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_with(
+        request: ListRequest,
+    ) -> crate::Result<impl futures::Stream<Item = crate::Result<Vec<HmacMeta>>>> {
+        crate::CLOUD_CLIENT.hmac_key().list_with(request).await
+    }
+
+    /// The synchronous equivalent of `HmacKey::list_with`, which collects all pages.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn list_with_sync(request: ListRequest) -> crate::Result<Vec<HmacMeta>> {
+        use futures::TryStreamExt;
+
+        Self::list_with(request).await?.try_concat().await
+    }
+
     /// Retrieves an HMAC key's metadata. Since the HmacKey is secret, this does not return a
     /// `HmacKey`, but a `HmacMeta`. This is a redacted version of a `HmacKey`, but with the secret
     /// data omitted.
@@ -205,23 +243,7 @@ impl HmacKey {
     /// # Ok(())
     /// # }
     pub async fn read(access_id: &str) -> crate::Result<HmacMeta> {
-        let url = format!(
-            "{}/projects/{}/hmacKeys/{}",
-            crate::BASE_URL,
-            crate::SERVICE_ACCOUNT.project_id,
-            access_id
-        );
-        let result: GoogleResponse<HmacMeta> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.hmac_key().read(access_id).await
     }
 
     /// The synchronous equivalent of `HmacKey::read`.
@@ -253,25 +275,10 @@ impl HmacKey {
     /// # Ok(())
     /// # }
     pub async fn update(access_id: &str, state: HmacState) -> crate::Result<HmacMeta> {
-        let url = format!(
-            "{}/projects/{}/hmacKeys/{}",
-            crate::BASE_URL,
-            crate::SERVICE_ACCOUNT.project_id,
-            access_id
-        );
-        serde_json::to_string(&UpdateMeta { state })?;
-        let result: GoogleResponse<HmacMeta> = crate::CLIENT
-            .put(&url)
-            .headers(crate::get_headers().await?)
-            .json(&UpdateMeta { state })
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .hmac_key()
+            .update(access_id, state)
+            .await
     }
 
     /// The synchronous equivalent of `HmacKey::update`.
@@ -302,22 +309,7 @@ impl HmacKey {
     /// # Ok(())
     /// # }
     pub async fn delete(access_id: &str) -> crate::Result<()> {
-        let url = format!(
-            "{}/projects/{}/hmacKeys/{}",
-            crate::BASE_URL,
-            crate::SERVICE_ACCOUNT.project_id,
-            access_id
-        );
-        let response = crate::CLIENT
-            .delete(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(crate::Error::Google(response.json().await?))
-        }
+        crate::CLOUD_CLIENT.hmac_key().delete(access_id).await
     }
 
     /// The synchronous equivalent of `HmacKey::delete`.
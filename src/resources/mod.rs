@@ -24,5 +24,5 @@ pub mod object_access_control;
 pub mod service_account;
 /// Used for parsing the `service-account-********.json` file.
 pub(crate) mod signature;
-/// The topic field of a `Notification`
-mod topic;
+// /// The topic field of a `Notification`
+// mod topic;
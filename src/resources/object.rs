@@ -1,8 +1,13 @@
-use crate::error::{Error, GoogleResponse};
+use crate::client::{ObjectStream, ResumableUpload};
+use crate::error::Error;
 pub use crate::resources::bucket::Owner;
-use crate::resources::common::ListResponse;
-use crate::resources::object_access_control::ObjectAccessControl;
-use futures::{stream, Stream, TryStream};
+pub use crate::resources::common::Projection;
+use crate::resources::{
+    bucket::StorageClass,
+    object_access_control::{NewObjectAccessControl, ObjectAccessControl},
+};
+use crate::{ByteRange, ContentRange, PostPolicy, SignedPostPolicy, SignedUrlOptions};
+use futures::{Stream, TryStream};
 use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 
 /// A resource representing a file in Google Cloud Storage.
@@ -104,6 +109,59 @@ pub struct CustomerEncrypton {
     pub key_sha256: String,
 }
 
+/// A model that can be used to create a new object together with its metadata, using
+/// `Object::create_with`. Note that `NewObject` implements `Default`, so you don't have to specify
+/// the fields you're not using.
+#[derive(Debug, PartialEq, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewObject {
+    /// The name of the object. Required.
+    pub name: String,
+    /// Content-Type of the object data. If an object is stored without a Content-Type, it is
+    /// served as application/octet-stream.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Content-Encoding of the object data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+    /// Content-Disposition of the object data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_disposition: Option<String>,
+    /// Content-Language of the object data.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_language: Option<String>,
+    /// Cache-Control directive for the object data. If omitted, and the object is accessible to
+    /// all anonymous users, the default will be public, max-age=3600.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    /// User-provided metadata, in key/value pairs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<std::collections::HashMap<String, String>>,
+    /// Access controls on the object.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acl: Option<Vec<NewObjectAccessControl>>,
+    /// Storage class of the object. Defaults to the default storage class of the bucket.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_class: Option<StorageClass>,
+    /// Whether or not the object is subject to a temporary hold.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temporary_hold: Option<bool>,
+    /// Whether or not the object is subject to an event-based hold.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub event_based_hold: Option<bool>,
+    /// MD5 hash of the data, encoded using base64. Google rejects the upload if the data does not
+    /// match this hash.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub md5_hash: Option<String>,
+    /// CRC32c checksum of the data, encoded using base64 in big-endian byte order. Google rejects
+    /// the upload if the data does not match this checksum.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crc32c: Option<String>,
+    /// Cloud KMS Key used to encrypt this object, if the object is encrypted by such a key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kms_key_name: Option<String>,
+}
+
 /// The request that is supplied to perform `Object::compose`.
 #[derive(Debug, PartialEq, serde::Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -139,21 +197,215 @@ pub struct ObjectPrecondition {
     pub if_generation_match: i64,
 }
 
-#[derive(Debug, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct ObjectList {
-    kind: String,
-    items: Vec<Object>,
+/// A page of the objects in a bucket, as returned by `Object::list_with`.
+#[derive(Debug, PartialEq)]
+pub struct ObjectList {
+    /// The objects on this page.
+    pub items: Vec<Object>,
+    /// The common prefixes of the object names that contain the delimiter after the prefix of the
+    /// `ListRequest`, which can be listed like directories. Empty unless a delimiter is set.
+    pub prefixes: Vec<String>,
+    /// The token that lists the next page, or `None` if this is the last page.
+    pub next_page_token: Option<String>,
+}
+
+/// A set of parameters that select the objects that are listed by `Object::list_with`.
+///
+/// ### Example
+/// ```rust
+/// use cloud_storage::object::ListRequest;
+///
+/// // the objects and the "directories" directly in `photos/2020/`
+/// let request = ListRequest::new().prefix("photos/2020/").delimiter("/");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListRequest {
+    pub(crate) prefix: Option<String>,
+    pub(crate) delimiter: Option<String>,
+    pub(crate) include_trailing_delimiter: bool,
+    pub(crate) versions: bool,
+    pub(crate) start_offset: Option<String>,
+    pub(crate) end_offset: Option<String>,
+    pub(crate) match_glob: Option<String>,
+    pub(crate) max_results: Option<u32>,
+    pub(crate) projection: Option<Projection>,
+    pub(crate) page_token: Option<String>,
+}
+
+impl ListRequest {
+    /// Create a new instance of `ListRequest`, which lists all objects. Equivalent to
+    /// `ListRequest::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only lists the objects whose names start with `prefix`.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    /// Groups the objects whose names contain `delimiter` after the prefix into the `prefixes` of
+    /// the `ObjectList`, instead of listing them. With the delimiter `/`, this lists a bucket like
+    /// a directory.
+    pub fn delimiter(mut self, delimiter: &str) -> Self {
+        self.delimiter = Some(delimiter.to_string());
+        self
+    }
+
+    /// Also lists the objects whose names end with the delimiter, such as the placeholder objects
+    /// that the Cloud Console creates for folders, besides adding them to the `prefixes`.
+    pub fn include_trailing_delimiter(mut self) -> Self {
+        self.include_trailing_delimiter = true;
+        self
+    }
+
+    /// Also lists the noncurrent generations of the objects, in buckets with versioning enabled.
+    pub fn versions(mut self) -> Self {
+        self.versions = true;
+        self
+    }
+
+    /// Only lists the objects whose names are lexicographically equal to or after `start_offset`.
+    pub fn start_offset(mut self, start_offset: &str) -> Self {
+        self.start_offset = Some(start_offset.to_string());
+        self
+    }
+
+    /// Only lists the objects whose names are lexicographically before `end_offset`.
+    pub fn end_offset(mut self, end_offset: &str) -> Self {
+        self.end_offset = Some(end_offset.to_string());
+        self
+    }
+
+    /// Only lists the objects whose names match the
+    /// [glob pattern](https://cloud.google.com/storage/docs/json_api/v1/objects/list#list-objects-and-prefixes-using-glob),
+    /// for example `**.png`.
+    pub fn match_glob(mut self, match_glob: &str) -> Self {
+        self.match_glob = Some(match_glob.to_string());
+        self
+    }
+
+    /// The maximum number of objects and prefixes on each page. Google returns at most 1000 per
+    /// page, which is also the default.
+    pub fn max_results(mut self, max_results: u32) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    /// Which properties of the objects are returned. Defaults to `Projection::NoAcl`.
+    pub fn projection(mut self, projection: Projection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Starts listing at the page of this token, which is the `next_page_token` of an
+    /// `ObjectList`.
+    pub fn page_token(mut self, page_token: &str) -> Self {
+        self.page_token = Some(page_token.to_string());
+        self
+    }
+
+    // The query parameters of the request, without the page token.
+    pub(crate) fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = Vec::new();
+        let strings = [
+            ("prefix", &self.prefix),
+            ("delimiter", &self.delimiter),
+            ("startOffset", &self.start_offset),
+            ("endOffset", &self.end_offset),
+            ("matchGlob", &self.match_glob),
+        ];
+        for (name, value) in strings.iter() {
+            if let Some(value) = value {
+                query.push((*name, value.clone()));
+            }
+        }
+        if self.include_trailing_delimiter {
+            query.push(("includeTrailingDelimiter", "true".to_string()));
+        }
+        if self.versions {
+            query.push(("versions", "true".to_string()));
+        }
+        if let Some(max_results) = self.max_results {
+            query.push(("maxResults", max_results.to_string()));
+        }
+        if let Some(projection) = self.projection {
+            query.push(("projection", projection.as_str().to_string()));
+        }
+        query
+    }
+}
+
+/// A set of parameters that change how `Object::rewrite_with` rewrites an object.
+#[derive(Debug, Default)]
+pub struct RewriteOptions {
+    pub(crate) storage_class: Option<StorageClass>,
+    pub(crate) kms_key_name: Option<String>,
+    pub(crate) max_bytes_rewritten_per_call: Option<u64>,
+}
+
+impl RewriteOptions {
+    /// Create a new instance of `RewriteOptions`. Equivalent to `RewriteOptions::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The storage class of the rewritten object. By default, the object gets the default storage
+    /// class of the destination bucket.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use cloud_storage::bucket::StorageClass;
+    /// use cloud_storage::object::RewriteOptions;
+    ///
+    /// let opts = RewriteOptions::new().storage_class(StorageClass::Coldline);
+    /// ```
+    pub fn storage_class(mut self, storage_class: StorageClass) -> Self {
+        self.storage_class = Some(storage_class);
+        self
+    }
+
+    /// The Cloud KMS key that is used to encrypt the rewritten object, in the form
+    /// `projects/{project}/locations/{location}/keyRings/{ring}/cryptoKeys/{key}`. By default, the
+    /// object is encrypted with the default key of the destination bucket, if it has one.
+    pub fn kms_key_name(mut self, kms_key_name: &str) -> Self {
+        self.kms_key_name = Some(kms_key_name.to_string());
+        self
+    }
+
+    /// The maximum number of bytes Google rewrites in a single call. Smaller values report
+    /// progress more often, at the cost of more calls. Google only applies this limit to rewrites
+    /// that change the location, storage class or encryption of the object, and requires it to be
+    /// a multiple of 1 MiB.
+    pub fn max_bytes_rewritten_per_call(mut self, max_bytes: u64) -> Self {
+        self.max_bytes_rewritten_per_call = Some(max_bytes);
+        self
+    }
+}
+
+/// The progress of a rewrite, as reported by Google after every call that rewrites part of the
+/// object.
+#[derive(Debug)]
+pub struct RewriteProgress {
+    /// The number of bytes that have been rewritten so far.
+    pub total_bytes_rewritten: u64,
+    /// The size of the object that is rewritten.
+    pub object_size: u64,
+    /// The rewritten object, once the rewrite is done.
+    pub resource: Option<Object>,
 }
 
 #[derive(Debug, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
-struct RewriteResponse {
-    kind: String,
-    total_bytes_rewritten: String,
-    object_size: String,
-    done: bool,
-    resource: Object,
+pub(crate) struct RewriteResponse {
+    #[serde(deserialize_with = "crate::from_str")]
+    pub(crate) total_bytes_rewritten: u64,
+    #[serde(deserialize_with = "crate::from_str")]
+    pub(crate) object_size: u64,
+    pub(crate) done: bool,
+    pub(crate) rewrite_token: Option<String>,
+    pub(crate) resource: Option<Object>,
 }
 
 impl Object {
@@ -178,30 +430,10 @@ impl Object {
         filename: &str,
         mime_type: &str,
     ) -> crate::Result<Self> {
-        use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
-
-        // has its own url for some reason
-        const BASE_URL: &str = "https://www.googleapis.com/upload/storage/v1/b";
-        let url = &format!(
-            "{}/{}/o?uploadType=media&name={}",
-            BASE_URL,
-            percent_encode(&bucket),
-            percent_encode(&filename),
-        );
-        let mut headers = crate::get_headers().await?;
-        headers.insert(CONTENT_TYPE, mime_type.parse()?);
-        headers.insert(CONTENT_LENGTH, file.len().to_string().parse()?);
-        let response = crate::CLIENT
-            .post(url)
-            .headers(headers)
-            .body(file)
-            .send()
-            .await?;
-        if response.status() == 200 {
-            Ok(serde_json::from_str(&response.text().await?)?)
-        } else {
-            Err(Error::new(&response.text().await?))
-        }
+        crate::CLOUD_CLIENT
+            .object()
+            .create(bucket, file, filename, mime_type)
+            .await
     }
 
     /// The synchronous equivalent of `Object::create`.
@@ -248,34 +480,10 @@ impl Object {
         S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
         bytes::Bytes: From<S::Ok>,
     {
-        use reqwest::header::{CONTENT_LENGTH, CONTENT_TYPE};
-
-        // has its own url for some reason
-        const BASE_URL: &str = "https://www.googleapis.com/upload/storage/v1/b";
-        let url = &format!(
-            "{}/{}/o?uploadType=media&name={}",
-            BASE_URL,
-            percent_encode(&bucket),
-            percent_encode(&filename),
-        );
-        let mut headers = crate::get_headers().await?;
-        headers.insert(CONTENT_TYPE, mime_type.parse()?);
-        if let Some(length) = length.into() {
-            headers.insert(CONTENT_LENGTH, length.into());
-        }
-
-        let body = reqwest::Body::wrap_stream(stream);
-        let response = crate::CLIENT
-            .post(url)
-            .headers(headers)
-            .body(body)
-            .send()
-            .await?;
-        if response.status() == 200 {
-            Ok(serde_json::from_str(&response.text().await?)?)
-        } else {
-            Err(Error::new(&response.text().await?))
-        }
+        crate::CLOUD_CLIENT
+            .object()
+            .create_streamed(bucket, stream, length, filename, mime_type)
+            .await
     }
 
     /// The async equivalent of `Object::create_streamed`.
@@ -295,12 +503,95 @@ impl Object {
         file.read_to_end(&mut buffer)
             .map_err(|e| Error::Other(e.to_string()))?;
 
-        let stream = stream::once(async { Ok::<_, Error>(buffer) });
+        let stream = futures::stream::once(async { Ok::<_, Error>(buffer) });
 
         Self::create_streamed(bucket, stream, length, filename, mime_type).await
     }
 
+    /// Create a new object and set its metadata in the same request. Unlike `Object::create`, this
+    /// allows setting fields such as `metadata`, `cache_control` or `storage_class` without an
+    /// additional call to `Object::update`.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::object::{NewObject, Object};
+    ///
+    /// let new_object = NewObject {
+    ///     name: "cat.png".to_string(),
+    ///     content_type: Some("image/png".to_string()),
+    ///     cache_control: Some("public, max-age=86400".to_string()),
+    ///     ..Default::default()
+    /// };
+    /// Object::create_with("cat-photos", vec![0, 1], &new_object).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_with(
+        bucket: &str,
+        file: Vec<u8>,
+        new_object: &NewObject,
+    ) -> crate::Result<Self> {
+        crate::CLOUD_CLIENT
+            .object()
+            .create_with(bucket, file, new_object)
+            .await
+    }
+
+    /// The synchronous equivalent of `Object::create_with`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn create_with_sync(
+        bucket: &str,
+        file: Vec<u8>,
+        new_object: &NewObject,
+    ) -> crate::Result<Self> {
+        Self::create_with(bucket, file, new_object).await
+    }
+
+    /// Starts a [resumable upload](https://cloud.google.com/storage/docs/resumable-uploads)
+    /// session for a new object. See `ObjectClient::start_resumable_upload`.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Object;
+    ///
+    /// let file = reqwest::Client::new()
+    ///     .get("https://my_domain.rs/nice_cat_video.mp4")
+    ///     .send()
+    ///     .await?
+    ///     .bytes_stream();
+    /// let upload = Object::start_resumable_upload("cat-videos", "cat.mp4", "video/mp4", None).await?;
+    /// println!("resume with {}", upload.session_uri());
+    /// upload.upload(0, file, None).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn start_resumable_upload(
+        bucket: &str,
+        filename: &str,
+        mime_type: &str,
+        length: impl Into<Option<u64>>,
+    ) -> crate::Result<ResumableUpload<'static>> {
+        crate::CLOUD_CLIENT
+            .object()
+            .start_resumable_upload(bucket, filename, mime_type, length)
+            .await
+    }
+
+    /// Continues a resumable upload session that was started earlier, possibly by another process.
+    /// See `ObjectClient::resume_upload`.
+    pub fn resume_upload(session_uri: &str) -> ResumableUpload<'static> {
+        crate::CLOUD_CLIENT.object().resume_upload(session_uri)
+    }
+
     /// Obtain a list of objects within this Bucket.
+    /// Every item of the stream is a whole page of objects; use `Object::list_stream` to list large
+    /// buckets one object at a time.
     /// ### Example
     /// ```no_run
     /// # #[tokio::main]
@@ -314,7 +605,7 @@ impl Object {
     pub async fn list(
         bucket: &str,
     ) -> Result<impl Stream<Item = Result<Vec<Self>, Error>> + '_, Error> {
-        Self::list_from(bucket, None).await
+        crate::CLOUD_CLIENT.object().list(bucket).await
     }
 
     /// The async equivalent of `Object::list`.
@@ -326,7 +617,7 @@ impl Object {
     pub async fn list_sync(bucket: &str) -> Result<Vec<Self>, Error> {
         use futures::TryStreamExt;
 
-        Self::list_from(bucket, None).await?.try_concat().await
+        Self::list(bucket).await?.try_concat().await
     }
 
     /// Obtain a list of objects by prefix within this Bucket .
@@ -344,7 +635,10 @@ impl Object {
         bucket: &'a str,
         prefix: &'a str,
     ) -> Result<impl Stream<Item = Result<Vec<Self>, Error>> + 'a, Error> {
-        Self::list_from(bucket, Some(prefix)).await
+        crate::CLOUD_CLIENT
+            .object()
+            .list_prefix(bucket, prefix)
+            .await
     }
 
     /// The async equivalent of `Object::list_prefix`.
@@ -356,74 +650,71 @@ impl Object {
     pub async fn list_prefix_sync(bucket: &str, prefix: &str) -> Result<Vec<Self>, Error> {
         use futures::TryStreamExt;
 
-        Self::list_from(bucket, Some(prefix))
-            .await?
-            .try_concat()
-            .await
+        Self::list_prefix(bucket, prefix).await?.try_concat().await
     }
 
-    async fn list_from<'a>(
-        bucket: &'a str,
-        prefix: Option<&'a str>,
-    ) -> Result<impl Stream<Item = Result<Vec<Self>, Error>> + 'a, Error> {
-        #[derive(Clone)]
-        enum ListState {
-            Start,
-            HasMore(String),
-            Done,
-        }
-        use ListState::*;
-
-        Ok(stream::unfold(ListState::Start, move |state| async move {
-            let url = format!("{}/b/{}/o", crate::BASE_URL, percent_encode(bucket));
-            let headers = match crate::get_headers().await {
-                Ok(h) => h,
-                Err(e) => return Some((Err(e), state)),
-            };
-
-            let mut query = match state.clone() {
-                HasMore(page_token) => vec![("pageToken", page_token)],
-                Done => return None,
-                Start => vec![],
-            };
-
-            if let Some(prefix) = prefix {
-                query.push(("prefix", prefix.to_string()));
-            };
-
-            let response = crate::CLIENT
-                .get(&url)
-                .query(&query)
-                .headers(headers)
-                .send()
-                .await;
-            let response = match response {
-                Ok(r) => r,
-                Err(e) => return Some((Err(e.into()), state)),
-            };
-
-            let json = match response.json().await {
-                Ok(json) => json,
-                Err(e) => return Some((Err(e.into()), state)),
-            };
-
-            let result: GoogleResponse<ListResponse<Self>> = json;
-
-            let response_body = match result {
-                GoogleResponse::Success(success) => success,
-                GoogleResponse::Error(e) => return Some((Err(e.into()), state)),
-            };
+    /// Lists the objects in `bucket` that are selected by `request`, one page at a time. Each
+    /// page also contains the common prefixes of the object names when the request sets a
+    /// delimiter.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::object::{ListRequest, Object, Projection};
+    /// use futures::TryStreamExt;
+    ///
+    /// let request = ListRequest::new()
+    ///     .versions()
+    ///     .match_glob("**.png")
+    ///     .projection(Projection::Full);
+    /// let pages: Vec<_> = Object::list_with("my_bucket", request).await?.try_collect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_with(
+        bucket: &str,
+        request: ListRequest,
+    ) -> Result<impl Stream<Item = Result<ObjectList, Error>> + '_, Error> {
+        crate::CLOUD_CLIENT
+            .object()
+            .list_with(bucket, request)
+            .await
+    }
 
-            let items = response_body.items;
+    /// Lists the objects in `bucket` that are selected by `request`, one object at a time, and
+    /// only fetches the next page once the previous one has been consumed. See `ObjectStream` for
+    /// how to checkpoint and resume the listing.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::object::{ListRequest, Object};
+    /// use futures::TryStreamExt;
+    ///
+    /// let mut objects = Object::list_stream("my_bucket", ListRequest::new().prefix("logs/"));
+    /// while let Some(object) = objects.try_next().await? {
+    ///     println!("{}", object.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn list_stream(bucket: &str, request: ListRequest) -> ObjectStream<'_> {
+        crate::CLOUD_CLIENT.object().list_stream(bucket, request)
+    }
 
-            let next_state = if let Some(page_token) = response_body.next_page_token {
-                HasMore(page_token)
-            } else {
-                Done
-            };
+    /// The synchronous equivalent of `Object::list_with`, which collects all pages.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn list_with_sync(
+        bucket: &str,
+        request: ListRequest,
+    ) -> Result<Vec<ObjectList>, Error> {
+        use futures::TryStreamExt;
 
-            Some((Ok(items), next_state))
-        }))
+        Self::list_with(bucket, request).await?.try_collect().await
     }
 
     /// Obtains a single object with the specified name in the specified bucket.
@@ -438,23 +729,7 @@ impl Object {
     /// # }
     /// ```
     pub async fn read(bucket: &str, file_name: &str) -> crate::Result<Self> {
-        let url = format!(
-            "{}/b/{}/o/{}",
-            crate::BASE_URL,
-            percent_encode(bucket),
-            percent_encode(file_name),
-        );
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.object().read(bucket, file_name).await
     }
 
     /// The synchronous equivalent of `Object::read`.
@@ -467,6 +742,43 @@ impl Object {
         Self::read(bucket, file_name).await
     }
 
+    /// Obtains the metadata of a `generation` of the object, which may be noncurrent.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Object;
+    ///
+    /// let object = Object::read_generation("my_bucket", "config.json", 1588778400000000).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn read_generation(
+        bucket: &str,
+        file_name: &str,
+        generation: i64,
+    ) -> crate::Result<Self> {
+        crate::CLOUD_CLIENT
+            .object()
+            .with_generation(generation)
+            .read(bucket, file_name)
+            .await
+    }
+
+    /// The synchronous equivalent of `Object::read_generation`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn read_generation_sync(
+        bucket: &str,
+        file_name: &str,
+        generation: i64,
+    ) -> crate::Result<Self> {
+        Self::read_generation(bucket, file_name, generation).await
+    }
+
     /// Download the content of the object with the specified name in the specified bucket.
     /// ### Example
     /// ```no_run
@@ -479,20 +791,10 @@ impl Object {
     /// # }
     /// ```
     pub async fn download(bucket: &str, file_name: &str) -> Result<Vec<u8>, Error> {
-        let url = format!(
-            "{}/b/{}/o/{}?alt=media",
-            crate::BASE_URL,
-            percent_encode(bucket),
-            percent_encode(file_name),
-        );
-        Ok(crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .bytes()
-            .await?
-            .to_vec())
+        crate::CLOUD_CLIENT
+            .object()
+            .download(bucket, file_name)
+            .await
     }
 
     /// The synchronous equivalent of `Object::download`.
@@ -505,6 +807,43 @@ impl Object {
         Self::download(bucket, file_name).await
     }
 
+    /// Downloads the content of a `generation` of the object, which may be noncurrent.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Object;
+    ///
+    /// let bytes = Object::download_generation("my_bucket", "config.json", 1588778400000000).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_generation(
+        bucket: &str,
+        file_name: &str,
+        generation: i64,
+    ) -> crate::Result<Vec<u8>> {
+        crate::CLOUD_CLIENT
+            .object()
+            .with_generation(generation)
+            .download(bucket, file_name)
+            .await
+    }
+
+    /// The synchronous equivalent of `Object::download_generation`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn download_generation_sync(
+        bucket: &str,
+        file_name: &str,
+        generation: i64,
+    ) -> crate::Result<Vec<u8>> {
+        Self::download_generation(bucket, file_name, generation).await
+    }
+
     /// Download the content of the object with the specified name in the specified bucket, without
     /// allocating the whole file into a vector.
     /// ### Example
@@ -528,24 +867,75 @@ impl Object {
         bucket: &str,
         file_name: &str,
     ) -> crate::Result<impl Stream<Item = crate::Result<u8>> + Unpin> {
-        use futures::{StreamExt, TryStreamExt};
-        let url = format!(
-            "{}/b/{}/o/{}?alt=media",
-            crate::BASE_URL,
-            percent_encode(bucket),
-            percent_encode(file_name),
-        );
-        let res = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?;
-        let size = res.content_length();
-        let bytes = res
-            .bytes_stream()
-            .map(|chunk| chunk.map(|c| futures::stream::iter(c.into_iter().map(Ok))))
-            .try_flatten();
-        Ok(SizedByteStream::new(bytes, size))
+        crate::CLOUD_CLIENT
+            .object()
+            .download_streamed(bucket, file_name)
+            .await
+    }
+
+    /// Download part of the content of the object with the specified name in the specified bucket.
+    /// Returns the downloaded bytes, together with the range they cover and the total size of the
+    /// object.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{ByteRange, Object};
+    ///
+    /// let (footer, range) = Object::download_range("my_bucket", "data.parquet", ByteRange::Suffix(8)).await?;
+    /// println!("the file is {:?} bytes long", range.total_size);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_range(
+        bucket: &str,
+        file_name: &str,
+        range: impl Into<ByteRange>,
+    ) -> crate::Result<(Vec<u8>, ContentRange)> {
+        crate::CLOUD_CLIENT
+            .object()
+            .download_range(bucket, file_name, range)
+            .await
+    }
+
+    /// The synchronous equivalent of `Object::download_range`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn download_range_sync(
+        bucket: &str,
+        file_name: &str,
+        range: impl Into<ByteRange>,
+    ) -> crate::Result<(Vec<u8>, ContentRange)> {
+        Self::download_range(bucket, file_name, range).await
+    }
+
+    /// Download part of the content of the object with the specified name in the specified bucket,
+    /// without allocating it into a vector. The range that the stream covers is available through
+    /// `SizedByteStream::content_range`.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Object;
+    /// use futures::TryStreamExt;
+    ///
+    /// let stream = Object::download_range_streamed("my_bucket", "big.log", 1024..).await?;
+    /// let rest: Vec<u8> = stream.try_collect().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn download_range_streamed(
+        bucket: &str,
+        file_name: &str,
+        range: impl Into<ByteRange>,
+    ) -> crate::Result<SizedByteStream<impl Stream<Item = crate::Result<u8>> + Unpin>> {
+        crate::CLOUD_CLIENT
+            .object()
+            .download_range_streamed(bucket, file_name, range)
+            .await
     }
 
     /// Obtains a single object with the specified name in the specified bucket.
@@ -562,24 +952,7 @@ impl Object {
     /// # }
     /// ```
     pub async fn update(&self) -> crate::Result<Self> {
-        let url = format!(
-            "{}/b/{}/o/{}",
-            crate::BASE_URL,
-            percent_encode(&self.bucket),
-            percent_encode(&self.name),
-        );
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .put(&url)
-            .headers(crate::get_headers().await?)
-            .json(&self)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT.object().update(self).await
     }
 
     /// The synchronous equivalent of `Object::download`.
@@ -604,32 +977,88 @@ impl Object {
     /// # }
     /// ```
     pub async fn delete(bucket: &str, file_name: &str) -> Result<(), Error> {
-        let url = format!(
-            "{}/b/{}/o/{}",
-            crate::BASE_URL,
-            percent_encode(bucket),
-            percent_encode(file_name),
-        );
-        let response = crate::CLIENT
-            .delete(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::Google(response.json().await?))
-        }
+        crate::CLOUD_CLIENT.object().delete(bucket, file_name).await
+    }
+
+    /// The synchronous equivalent of `Object::delete`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn delete_sync(bucket: &str, file_name: &str) -> Result<(), Error> {
+        Self::delete(bucket, file_name).await
+    }
+
+    /// Permanently deletes a `generation` of the object, which may be noncurrent.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Object;
+    ///
+    /// Object::delete_generation("my_bucket", "config.json", 1588778400000000).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn delete_generation(
+        bucket: &str,
+        file_name: &str,
+        generation: i64,
+    ) -> Result<(), Error> {
+        crate::CLOUD_CLIENT
+            .object()
+            .with_generation(generation)
+            .delete(bucket, file_name)
+            .await
+    }
+
+    /// The synchronous equivalent of `Object::delete_generation`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn delete_generation_sync(
+        bucket: &str,
+        file_name: &str,
+        generation: i64,
+    ) -> Result<(), Error> {
+        Self::delete_generation(bucket, file_name, generation).await
+    }
+
+    /// Restores a noncurrent `generation` of the object by copying it over the live version, but
+    /// only if the live version does not change while it is restored. See
+    /// `ObjectClient::restore`.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Object;
+    ///
+    /// let restored = Object::restore("my_bucket", "config.json", 1588778400000000).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn restore(bucket: &str, file_name: &str, generation: i64) -> crate::Result<Self> {
+        crate::CLOUD_CLIENT
+            .object()
+            .restore(bucket, file_name, generation)
+            .await
     }
 
-    /// The synchronous equivalent of `Object::delete`.
+    /// The synchronous equivalent of `Object::restore`.
     ///
     /// ### Features
     /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
     #[cfg(feature = "sync")]
     #[tokio::main]
-    pub async fn delete_sync(bucket: &str, file_name: &str) -> Result<(), Error> {
-        Self::delete(bucket, file_name).await
+    pub async fn restore_sync(
+        bucket: &str,
+        file_name: &str,
+        generation: i64,
+    ) -> crate::Result<Self> {
+        Self::restore(bucket, file_name, generation).await
     }
 
     /// Obtains a single object with the specified name in the specified bucket.
@@ -667,24 +1096,10 @@ impl Object {
         req: &ComposeRequest,
         destination_object: &str,
     ) -> crate::Result<Self> {
-        let url = format!(
-            "{}/b/{}/o/{}/compose",
-            crate::BASE_URL,
-            percent_encode(&bucket),
-            percent_encode(&destination_object)
-        );
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .post(&url)
-            .headers(crate::get_headers().await?)
-            .json(req)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .object()
+            .compose(bucket, req, destination_object)
+            .await
     }
 
     /// The synchronous equivalent of `Object::compose`.
@@ -715,29 +1130,10 @@ impl Object {
     /// # }
     /// ```
     pub async fn copy(&self, destination_bucket: &str, path: &str) -> crate::Result<Self> {
-        use reqwest::header::CONTENT_LENGTH;
-
-        let url = format!(
-            "{base}/b/{sBucket}/o/{sObject}/copyTo/b/{dBucket}/o/{dObject}",
-            base = crate::BASE_URL,
-            sBucket = percent_encode(&self.bucket),
-            sObject = percent_encode(&self.name),
-            dBucket = percent_encode(&destination_bucket),
-            dObject = percent_encode(&path),
-        );
-        let mut headers = crate::get_headers().await?;
-        headers.insert(CONTENT_LENGTH, "0".parse()?);
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .post(&url)
-            .headers(headers)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .object()
+            .copy(self, destination_bucket, path)
+            .await
     }
 
     /// The synchronous equivalent of `Object::copy`.
@@ -750,14 +1146,9 @@ impl Object {
         self.copy(destination_bucket, path).await
     }
 
-    /// Moves a file from the current location to the target bucket and path.
-    ///
-    /// ## Limitations
-    /// This function does not yet support rewriting objects to another
-    /// * Geographical Location,
-    /// * Encryption,
-    /// * Storage class.
-    /// These limitations mean that for now, the rewrite and the copy methods do the same thing.
+    /// Rewrites this object to the target bucket and path. Unlike `Object::copy`, a rewrite can
+    /// move objects between locations and storage classes, which Google may perform in several
+    /// calls. This function returns once the whole object has been rewritten.
     /// ### Example
     /// ```no_run
     /// # #[tokio::main]
@@ -771,29 +1162,10 @@ impl Object {
     /// # }
     /// ```
     pub async fn rewrite(&self, destination_bucket: &str, path: &str) -> crate::Result<Self> {
-        use reqwest::header::CONTENT_LENGTH;
-
-        let url = format!(
-            "{base}/b/{sBucket}/o/{sObject}/rewriteTo/b/{dBucket}/o/{dObject}",
-            base = crate::BASE_URL,
-            sBucket = percent_encode(&self.bucket),
-            sObject = percent_encode(&self.name),
-            dBucket = percent_encode(destination_bucket),
-            dObject = percent_encode(path),
-        );
-        let mut headers = crate::get_headers().await?;
-        headers.insert(CONTENT_LENGTH, "0".parse()?);
-        let result: GoogleResponse<RewriteResponse> = crate::CLIENT
-            .post(&url)
-            .headers(headers)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s.resource),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .object()
+            .rewrite(self, destination_bucket, path)
+            .await
     }
 
     /// The synchronous equivalent of `Object::rewrite`.
@@ -806,9 +1178,84 @@ impl Object {
         self.rewrite(destination_bucket, path).await
     }
 
+    /// Rewrites this object to the target bucket and path, changing its storage class or
+    /// encryption key as specified in `options`.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::bucket::StorageClass;
+    /// use cloud_storage::object::{Object, RewriteOptions};
+    ///
+    /// let obj1 = Object::read("my_bucket", "file1").await?;
+    /// let opts = RewriteOptions::new().storage_class(StorageClass::Coldline);
+    /// let obj2 = obj1.rewrite_with("my_archive_bucket", "file1", &opts).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn rewrite_with(
+        &self,
+        destination_bucket: &str,
+        path: &str,
+        options: &RewriteOptions,
+    ) -> crate::Result<Self> {
+        crate::CLOUD_CLIENT
+            .object()
+            .rewrite_with(self, destination_bucket, path, options)
+            .await
+    }
+
+    /// The synchronous equivalent of `Object::rewrite_with`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn rewrite_with_sync(
+        &self,
+        destination_bucket: &str,
+        path: &str,
+        options: &RewriteOptions,
+    ) -> crate::Result<Self> {
+        self.rewrite_with(destination_bucket, path, options).await
+    }
+
+    /// Rewrites this object to the target bucket and path, yielding the progress of the rewrite
+    /// after every call to Google. The last item contains the rewritten object.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::object::{Object, RewriteOptions};
+    /// use futures::TryStreamExt;
+    ///
+    /// let obj1 = Object::read("my_bucket", "file1").await?;
+    /// let opts = RewriteOptions::new().max_bytes_rewritten_per_call(64 * 1024 * 1024);
+    /// let progress = obj1.rewrite_progress("my_other_bucket", "file2", &opts).await?;
+    /// let mut progress = Box::pin(progress);
+    /// while let Some(progress) = progress.try_next().await? {
+    ///     println!("{} of {} bytes", progress.total_bytes_rewritten, progress.object_size);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn rewrite_progress(
+        &self,
+        destination_bucket: &str,
+        path: &str,
+        options: &RewriteOptions,
+    ) -> crate::Result<impl Stream<Item = crate::Result<RewriteProgress>>> {
+        crate::CLOUD_CLIENT
+            .object()
+            .rewrite_progress(self, destination_bucket, path, options)
+            .await
+    }
+
     /// Creates a [Signed Url](https://cloud.google.com/storage/docs/access-control/signed-urls)
     /// which is valid for `duration` seconds, and lets the posessor download the file contents
-    /// without any authentication.
+    /// without any authentication. The url is signed with the private key of the service account
+    /// of the default client. Without a key, use `Object::signed_url`, which can sign with the IAM
+    /// Credentials API.
     /// ### Example
     /// ```no_run
     /// # #[tokio::main]
@@ -823,12 +1270,14 @@ impl Object {
     /// # }
     /// ```
     pub fn download_url(&self, duration: u32) -> crate::Result<String> {
-        self.sign(&self.name, duration, "GET", None)
+        self.download_url_with(duration, crate::DownloadOptions::new())
     }
 
     /// Creates a [Signed Url](https://cloud.google.com/storage/docs/access-control/signed-urls)
     /// which is valid for `duration` seconds, and lets the posessor download the file contents
-    /// without any authentication.
+    /// without any authentication. The url is signed with the private key of the service account
+    /// of the default client. Without a key, use `Object::signed_url`, which can sign with the IAM
+    /// Credentials API.
     /// ### Example
     /// ```no_run
     /// # #[tokio::main]
@@ -847,171 +1296,133 @@ impl Object {
         duration: u32,
         opts: crate::DownloadOptions,
     ) -> crate::Result<String> {
-        self.sign(&self.name, duration, "GET", opts.content_disposition)
+        let mut options = SignedUrlOptions::get();
+        if let Some(content_disposition) = opts.content_disposition {
+            options = options.response_content_disposition(&content_disposition);
+        }
+        crate::CLOUD_CLIENT
+            .url_signer()
+            .sign_blocking(&self.bucket, &self.name, duration, &options)
     }
 
-    // /// Creates a [Signed Url](https://cloud.google.com/storage/docs/access-control/signed-urls)
-    // /// which is valid for `duration` seconds, and lets the posessor upload new file contents.
-    // /// without any authentication.
-    // pub fn upload_url(&self, duration: u32) -> crate::Result<String> {
-    //     self.sign(&self.name, duration, "POST")
-    // }
+    /// Creates a [Signed Url](https://cloud.google.com/storage/docs/access-control/signed-urls)
+    /// which is valid for `duration` seconds, and lets the posessor upload new file contents to
+    /// `file_name` in `bucket` with a `PUT` request, without any authentication.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::Object;
+    ///
+    /// let url = Object::upload_url("my_bucket", "uploads/file1", 300).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn upload_url(bucket: &str, file_name: &str, duration: u32) -> crate::Result<String> {
+        Self::signed_url(bucket, file_name, duration, &SignedUrlOptions::put()).await
+    }
 
-    #[inline(always)]
-    fn sign(
-        &self,
-        file_path: &str,
+    /// The synchronous equivalent of `Object::upload_url`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn upload_url_sync(
+        bucket: &str,
+        file_name: &str,
         duration: u32,
-        http_verb: &str,
-        content_disposition: Option<String>,
     ) -> crate::Result<String> {
-        use openssl::sha;
-
-        if duration > 604800 {
-            let msg = format!(
-                "duration may not be greater than 604800, but was {}",
-                duration
-            );
-            return Err(Error::Other(msg));
-        }
-
-        // 0 Sort and construct the canonical headers
-        let mut headers = vec![];
-        headers.push(("host".to_string(), "storage.googleapis.com".to_string()));
-        headers.sort_unstable_by(|(k1, _), (k2, _)| k1.cmp(&k2));
-        let canonical_headers: String = headers
-            .iter()
-            .map(|(k, v)| format!("{}:{}", k.to_lowercase(), v.to_lowercase()))
-            .collect::<Vec<String>>()
-            .join("\n");
-        let signed_headers = headers
-            .iter()
-            .map(|(k, _)| k.to_lowercase())
-            .collect::<Vec<String>>()
-            .join(";");
-
-        // 1 construct the canonical request
-        let issue_date = chrono::Utc::now();
-        let file_path = self.path_to_resource(file_path);
-        let query_string = Self::get_canonical_query_string(
-            &issue_date,
-            duration,
-            &signed_headers,
-            content_disposition,
-        );
-        let canonical_request =
-            self.get_canonical_request(&file_path, &query_string, http_verb, &canonical_headers);
-
-        // 2 get hex encoded SHA256 hash the canonical request
-        let hash = sha::sha256(canonical_request.as_bytes());
-        let hex_hash = hex::encode(hash);
-
-        // 3 construct the string to sign
-        let string_to_sign = format!(
-            "{signing_algorithm}\n\
-            {current_datetime}\n\
-            {credential_scope}\n\
-            {hashed_canonical_request}",
-            signing_algorithm = "GOOG4-RSA-SHA256",
-            current_datetime = issue_date.format("%Y%m%dT%H%M%SZ"),
-            credential_scope = Self::get_credential_scope(&issue_date),
-            hashed_canonical_request = hex_hash,
-        );
-
-        // 4 sign the string to sign with RSA - SHA256
-        let buffer = Self::sign_str(&string_to_sign)?;
-        let signature = hex::encode(&buffer);
-
-        // 5 construct the signed url
-        Ok(format!(
-            "https://storage.googleapis.com{path_to_resource}?\
-            {query_string}&\
-            X-Goog-Signature={request_signature}",
-            path_to_resource = file_path,
-            query_string = query_string,
-            request_signature = signature,
-        ))
-    }
-
-    #[inline(always)]
-    fn get_canonical_request(
-        &self,
-        path: &str,
-        query_string: &str,
-        http_verb: &str,
-        headers: &str,
-    ) -> String {
-        format!(
-            "{http_verb}\n\
-            {path_to_resource}\n\
-            {canonical_query_string}\n\
-            {canonical_headers}\n\
-            \n\
-            {signed_headers}\n\
-            {payload}",
-            http_verb = http_verb,
-            path_to_resource = path,
-            canonical_query_string = query_string,
-            // canonical_headers = "host:storage.googleapis.com",
-            canonical_headers = headers,
-            signed_headers = "host",
-            payload = "UNSIGNED-PAYLOAD",
-        )
+        Self::upload_url(bucket, file_name, duration).await
     }
 
-    #[inline(always)]
-    fn get_canonical_query_string(
-        date: &chrono::DateTime<chrono::Utc>,
-        exp: u32,
-        headers: &str,
-        content_disposition: Option<String>,
-    ) -> String {
-        let credential = format!(
-            "{authorizer}/{scope}",
-            authorizer = crate::SERVICE_ACCOUNT.client_email,
-            scope = Self::get_credential_scope(date),
-        );
-        let mut s = format!(
-            "X-Goog-Algorithm={algo}&\
-            X-Goog-Credential={cred}&\
-            X-Goog-Date={date}&\
-            X-Goog-Expires={exp}&\
-            X-Goog-SignedHeaders={signed}",
-            algo = "GOOG4-RSA-SHA256",
-            cred = percent_encode(&credential),
-            date = date.format("%Y%m%dT%H%M%SZ"),
-            exp = exp,
-            // signed="host",
-            signed = headers,
-        );
-        if let Some(cd) = content_disposition {
-            s.push_str(&format!("&response-content-disposition={}", cd));
-        }
-        s
+    /// Creates a [Signed Url](https://cloud.google.com/storage/docs/access-control/signed-urls)
+    /// which is valid for `duration` seconds, and lets the posessor make the request described by
+    /// `options` for `file_name` in `bucket` without any authentication. The object does not need
+    /// to exist, so this can be used to let others upload objects.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Object, SignedUrlOptions};
+    ///
+    /// let options = SignedUrlOptions::put()
+    ///     .content_type("image/png")
+    ///     .content_length_range(0, 10_000_000);
+    /// let url = Object::signed_url("my_bucket", "avatars/user1.png", 300, &options).await?;
+    /// // the upload must send `content-type: image/png` and
+    /// // `x-goog-content-length-range: 0,10000000`
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn signed_url(
+        bucket: &str,
+        file_name: &str,
+        duration: u32,
+        options: &SignedUrlOptions,
+    ) -> crate::Result<String> {
+        crate::CLOUD_CLIENT
+            .object()
+            .signed_url(bucket, file_name, duration, options)
+            .await
     }
 
-    #[inline(always)]
-    fn path_to_resource(&self, path: &str) -> String {
-        format!(
-            "/{bucket}/{file_path}",
-            bucket = self.bucket,
-            file_path = percent_encode_noslash(path),
-        )
+    /// The synchronous equivalent of `Object::signed_url`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn signed_url_sync(
+        bucket: &str,
+        file_name: &str,
+        duration: u32,
+        options: &SignedUrlOptions,
+    ) -> crate::Result<String> {
+        Self::signed_url(bucket, file_name, duration, options).await
     }
 
-    #[inline(always)]
-    fn get_credential_scope(date: &chrono::DateTime<chrono::Utc>) -> String {
-        format!("{}/henk/storage/goog4_request", date.format("%Y%m%d"))
+    /// Signs a `PostPolicy`, which lets browsers upload an object with an HTML form for the next
+    /// `duration` seconds without any authentication.
+    /// ### Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// use cloud_storage::{Object, PostPolicy};
+    ///
+    /// let policy = PostPolicy::new("my_bucket", "uploads/${filename}")
+    ///     .key_starts_with("uploads/")
+    ///     .content_length_range(0, 10_000_000);
+    /// let signed = Object::post_policy(&policy, 600).await?;
+    /// println!("<form action=\"{}\" method=\"post\" enctype=\"multipart/form-data\">", signed.url);
+    /// for (name, value) in &signed.fields {
+    ///     println!("<input type=\"hidden\" name=\"{}\" value=\"{}\">", name, value);
+    /// }
+    /// println!("<input type=\"file\" name=\"file\"></form>");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn post_policy(
+        policy: &PostPolicy,
+        duration: u32,
+    ) -> crate::Result<SignedPostPolicy> {
+        crate::CLOUD_CLIENT
+            .object()
+            .post_policy(policy, duration)
+            .await
     }
 
-    #[inline(always)]
-    fn sign_str(message: &str) -> Result<Vec<u8>, Error> {
-        use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
-
-        let key = PKey::private_key_from_pem(crate::SERVICE_ACCOUNT.private_key.as_bytes())?;
-        let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
-        signer.update(message.as_bytes())?;
-        Ok(signer.sign_to_vec()?)
+    /// The synchronous equivalent of `Object::post_policy`.
+    ///
+    /// ### Features
+    /// This function requires that the feature flag `sync` is enabled in `Cargo.toml`.
+    #[cfg(feature = "sync")]
+    #[tokio::main]
+    pub async fn post_policy_sync(
+        policy: &PostPolicy,
+        duration: u32,
+    ) -> crate::Result<SignedPostPolicy> {
+        Self::post_policy(policy, duration).await
     }
 }
 
@@ -1021,22 +1432,59 @@ const ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
     .remove(b'.')
     .remove(b'_');
 
-const NOSLASH_ENCODE_SET: &AsciiSet = &ENCODE_SET.remove(b'/').remove(b'~');
+pub(crate) fn percent_encode(input: &str) -> String {
+    utf8_percent_encode(input, ENCODE_SET).to_string()
+}
 
-// We need to be able to percent encode stuff, but without touching the slashes in filenames. To
-// this end we create an implementation that does this, without touching the slashes.
-fn percent_encode_noslash(input: &str) -> String {
-    utf8_percent_encode(input, NOSLASH_ENCODE_SET).to_string()
+/// A wrapper around a downloaded object's byte stream that provides a useful `size_hint`.
+pub struct SizedByteStream<S: Stream<Item = crate::Result<u8>> + Unpin> {
+    size: Option<u64>,
+    content_range: Option<ContentRange>,
+    bytes: S,
 }
 
-fn percent_encode(input: &str) -> String {
-    utf8_percent_encode(input, ENCODE_SET).to_string()
+impl<S: Stream<Item = crate::Result<u8>> + Unpin> SizedByteStream<S> {
+    pub(crate) fn new(bytes: S, size: Option<u64>) -> Self {
+        Self {
+            bytes,
+            size,
+            content_range: None,
+        }
+    }
+
+    pub(crate) fn with_content_range(mut self, content_range: ContentRange) -> Self {
+        self.content_range = Some(content_range);
+        self
+    }
+
+    /// The part of the object that this stream yields, if it was obtained by a ranged download.
+    pub fn content_range(&self) -> Option<ContentRange> {
+        self.content_range
+    }
+}
+
+impl<S: Stream<Item = crate::Result<u8>> + Unpin> Stream for SizedByteStream<S> {
+    type Item = crate::Result<u8>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut futures::task::Context,
+    ) -> futures::task::Poll<Option<Self::Item>> {
+        futures::StreamExt::poll_next_unpin(&mut self.bytes, cx)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let size = self
+            .size
+            .and_then(|s| std::convert::TryInto::try_into(s).ok());
+        (size.unwrap_or(0), size)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use futures::{StreamExt, TryStreamExt};
+    use futures::{stream, StreamExt, TryStreamExt};
 
     #[tokio::test]
     async fn create() -> Result<(), Box<dyn std::error::Error>> {
@@ -1062,6 +1510,25 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn create_with() -> Result<(), Box<dyn std::error::Error>> {
+        let bucket = crate::read_test_bucket().await;
+        let mut metadata = std::collections::HashMap::new();
+        metadata.insert("key".to_string(), "value".to_string());
+        let new_object = NewObject {
+            name: "test-create-with".to_string(),
+            content_type: Some("text/plain".to_string()),
+            cache_control: Some("no-cache".to_string()),
+            metadata: Some(metadata.clone()),
+            ..Default::default()
+        };
+        let obj = Object::create_with(&bucket.name, vec![0, 1], &new_object).await?;
+        assert_eq!(obj.size, 2);
+        assert_eq!(obj.cache_control.as_deref(), Some("no-cache"));
+        assert_eq!(obj.metadata, Some(metadata));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn list() -> Result<(), Box<dyn std::error::Error>> {
         let test_bucket = crate::read_test_bucket().await;
@@ -1101,6 +1568,31 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn list_with_delimiter() -> Result<(), Box<dyn std::error::Error>> {
+        use futures::TryStreamExt;
+
+        let test_bucket = crate::read_test_bucket().await;
+        for name in &["test-list-with/1", "test-list-with/sub/1"] {
+            Object::create(&test_bucket.name, vec![0, 1], name, "text/plain").await?;
+        }
+
+        let request = ListRequest::new().prefix("test-list-with/").delimiter("/");
+        let pages: Vec<_> = Object::list_with(&test_bucket.name, request)
+            .await?
+            .try_collect()
+            .await?;
+        let names: Vec<_> = pages
+            .iter()
+            .flat_map(|p| &p.items)
+            .map(|o| &o.name)
+            .collect();
+        let prefixes: Vec<_> = pages.iter().flat_map(|p| &p.prefixes).collect();
+        assert_eq!(names, ["test-list-with/1"]);
+        assert_eq!(prefixes, ["test-list-with/sub/"]);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn read() -> Result<(), Box<dyn std::error::Error>> {
         let bucket = crate::read_test_bucket().await;
@@ -1127,6 +1619,30 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn download_range() -> Result<(), Box<dyn std::error::Error>> {
+        let bucket = crate::read_test_bucket().await;
+        let content = b"hello world";
+        Object::create(
+            &bucket.name,
+            content.to_vec(),
+            "test-download-range",
+            "application/octet-stream",
+        )
+        .await?;
+
+        let (data, range) =
+            Object::download_range(&bucket.name, "test-download-range", 6..).await?;
+        assert_eq!(data, b"world");
+        assert_eq!(range.total_size, Some(11));
+        let (data, _) =
+            Object::download_range(&bucket.name, "test-download-range", ByteRange::Suffix(3))
+                .await?;
+        assert_eq!(data, b"rld");
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn download_streamed() -> Result<(), Box<dyn std::error::Error>> {
         let bucket = crate::read_test_bucket().await;
@@ -1141,7 +1657,7 @@ mod tests {
 
         let mut result = Object::download_streamed(&bucket.name, "test-download").await?;
         let mut data = Vec::new();
-        for part in result.next().await {
+        while let Some(part) = result.next().await {
             data.push(part?);
         }
         // let data = data.next().await.flat_map(|part| part.into_iter()).collect();
@@ -1256,11 +1772,24 @@ mod tests {
         let obj = Object::create(&bucket.name, vec![0, 1], "test-rewrite", "text/plain").await?;
         let obj = obj.rewrite(&bucket.name, "test-rewritten").await?;
         let url = obj.download_url(100)?;
-        let download = crate::CLIENT.head(&url).send().await?;
+        let download = reqwest::Client::new().head(&url).send().await?;
         assert_eq!(download.status().as_u16(), 200);
         Ok(())
     }
 
+    #[tokio::test]
+    async fn rewrite_with() -> Result<(), Box<dyn std::error::Error>> {
+        let bucket = crate::read_test_bucket().await;
+        let obj = Object::create(&bucket.name, vec![0, 1], "test-rewrite", "text/plain").await?;
+        let options = RewriteOptions::new().storage_class(StorageClass::Nearline);
+        let obj = obj
+            .rewrite_with(&bucket.name, "test-rewritten-nearline", &options)
+            .await?;
+        assert_eq!(obj.storage_class, "NEARLINE");
+        assert_eq!(obj.content_type.as_deref(), Some("text/plain"));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_url_encoding() -> Result<(), Box<dyn std::error::Error>> {
         let bucket = crate::read_test_bucket().await;
@@ -1274,9 +1803,9 @@ mod tests {
         ];
         for name in &complicated_names {
             let _obj = Object::create(&bucket.name, vec![0, 1], name, "text/plain").await?;
-            let obj = Object::read(&bucket.name, &name).await.unwrap();
+            let obj = Object::read(&bucket.name, name).await.unwrap();
             let url = obj.download_url(100)?;
-            let download = crate::CLIENT.head(&url).send().await?;
+            let download = reqwest::Client::new().head(&url).send().await?;
             assert_eq!(download.status().as_u16(), 200);
         }
         Ok(())
@@ -1295,6 +1824,35 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_upload_url() -> Result<(), Box<dyn std::error::Error>> {
+        let bucket = crate::read_test_bucket().await;
+        let client = reqwest::Client::new();
+        let options = SignedUrlOptions::put().content_type("text/plain");
+        let url = Object::signed_url(&bucket.name, "test-upload-url", 100, &options).await?;
+
+        let upload = client
+            .put(&url)
+            .header("content-type", "text/plain")
+            .body(vec![0, 1])
+            .send()
+            .await?;
+        assert_eq!(upload.status().as_u16(), 200);
+        // the content type is bound into the signature
+        let upload = client.put(&url).body(vec![0, 1]).send().await?;
+        assert_eq!(upload.status().as_u16(), 403);
+
+        let url = Object::signed_url(
+            &bucket.name,
+            "test-upload-url",
+            100,
+            &SignedUrlOptions::delete(),
+        )
+        .await?;
+        assert!(client.delete(&url).send().await?.status().is_success());
+        Ok(())
+    }
+
     #[cfg(feature = "sync")]
     mod sync {
         use super::*;
@@ -1306,6 +1864,18 @@ mod tests {
             Ok(())
         }
 
+        #[test]
+        fn create_with() -> Result<(), Box<dyn std::error::Error>> {
+            let bucket = crate::read_test_bucket_sync();
+            let new_object = NewObject {
+                name: "test-create-with".to_string(),
+                content_type: Some("text/plain".to_string()),
+                ..Default::default()
+            };
+            Object::create_with_sync(&bucket.name, vec![0, 1], &new_object)?;
+            Ok(())
+        }
+
         #[test]
         fn create_streamed() -> Result<(), Box<dyn std::error::Error>> {
             let bucket = crate::read_test_bucket_sync();
@@ -1481,7 +2051,7 @@ mod tests {
             ];
             for name in &complicated_names {
                 let _obj = Object::create_sync(&bucket.name, vec![0, 1], name, "text/plain")?;
-                let obj = Object::read_sync(&bucket.name, &name).unwrap();
+                let obj = Object::read_sync(&bucket.name, name).unwrap();
                 let url = obj.download_url(100)?;
                 let client = reqwest::blocking::Client::new();
                 let download = client.head(&url).send()?;
@@ -1491,33 +2061,3 @@ mod tests {
         }
     }
 }
-
-/// A wrapper around a downloaded object's byte stream that provides a useful `size_hint`.
-pub struct SizedByteStream<S: Stream<Item = crate::Result<u8>> + Unpin> {
-    size: Option<u64>,
-    bytes: S,
-}
-
-impl<S: Stream<Item = crate::Result<u8>> + Unpin> SizedByteStream<S> {
-    fn new(bytes: S, size: Option<u64>) -> Self {
-        Self { bytes, size }
-    }
-}
-
-impl<S: Stream<Item = crate::Result<u8>> + Unpin> Stream for SizedByteStream<S> {
-    type Item = crate::Result<u8>;
-
-    fn poll_next(
-        mut self: std::pin::Pin<&mut Self>,
-        cx: &mut futures::task::Context,
-    ) -> futures::task::Poll<Option<Self::Item>> {
-        futures::StreamExt::poll_next_unpin(&mut self.bytes, cx)
-    }
-
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        let size = self
-            .size
-            .and_then(|s| std::convert::TryInto::try_into(s).ok());
-        (size.unwrap_or(0), size)
-    }
-}
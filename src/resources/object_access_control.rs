@@ -1,7 +1,5 @@
 #![allow(unused_imports)]
 
-use crate::error::GoogleResponse;
-use crate::resources::common::ListResponse;
 pub use crate::resources::common::{Entity, ProjectTeam, Role};
 
 /// The ObjectAccessControls resources represent the Access Control Lists (ACLs) for objects within
@@ -115,19 +113,10 @@ impl ObjectAccessControl {
         object: &str,
         new_object_access_control: &NewObjectAccessControl,
     ) -> crate::Result<Self> {
-        let url = format!("{}/b/{}/o/{}/acl", crate::BASE_URL, bucket, object);
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .post(&url)
-            .headers(crate::get_headers().await?)
-            .json(new_object_access_control)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .object_access_control()
+            .create(bucket, object, new_object_access_control)
+            .await
     }
 
     /// The sync equivalent of `ObjectAccessControl::create`.
@@ -151,18 +140,10 @@ impl ObjectAccessControl {
     /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
     /// control access instead.
     pub async fn list(bucket: &str, object: &str) -> crate::Result<Vec<Self>> {
-        let url = format!("{}/b/{}/o/{}/acl", crate::BASE_URL, bucket, object);
-        let result: GoogleResponse<ListResponse<Self>> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s.items),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .object_access_control()
+            .list(bucket, object)
+            .await
     }
 
     /// The sync equivalent of `ObjectAccessControl::list`.
@@ -182,24 +163,10 @@ impl ObjectAccessControl {
     /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
     /// control access instead.
     pub async fn read(bucket: &str, object: &str, entity: &Entity) -> crate::Result<Self> {
-        let url = format!(
-            "{}/b/{}/o/{}/acl/{}",
-            crate::BASE_URL,
-            bucket,
-            object,
-            entity
-        );
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .get(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .object_access_control()
+            .read(bucket, object, entity)
+            .await
     }
 
     /// The sync equivalent of `ObjectAccessControl::read`.
@@ -219,25 +186,10 @@ impl ObjectAccessControl {
     /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
     /// control access instead.
     pub async fn update(&self) -> crate::Result<Self> {
-        let url = format!(
-            "{}/b/{}/o/{}/acl/{}",
-            crate::BASE_URL,
-            self.bucket,
-            self.object,
-            self.entity,
-        );
-        let result: GoogleResponse<Self> = crate::CLIENT
-            .put(&url)
-            .headers(crate::get_headers().await?)
-            .json(self)
-            .send()
-            .await?
-            .json()
-            .await?;
-        match result {
-            GoogleResponse::Success(s) => Ok(s),
-            GoogleResponse::Error(e) => Err(e.into()),
-        }
+        crate::CLOUD_CLIENT
+            .object_access_control()
+            .update(self)
+            .await
     }
 
     /// The sync equivalent of `ObjectAccessControl::update`.
@@ -257,23 +209,10 @@ impl ObjectAccessControl {
     /// bucket-level access enabled. Use `Bucket::get_iam_policy` and `Bucket::set_iam_policy` to
     /// control access instead.
     pub async fn delete(self) -> crate::Result<()> {
-        let url = format!(
-            "{}/b/{}/o/{}/acl/{}",
-            crate::BASE_URL,
-            self.bucket,
-            self.object,
-            self.entity,
-        );
-        let response = crate::CLIENT
-            .delete(&url)
-            .headers(crate::get_headers().await?)
-            .send()
-            .await?;
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(crate::Error::Google(response.json().await?))
-        }
+        crate::CLOUD_CLIENT
+            .object_access_control()
+            .delete(self)
+            .await
     }
 
     /// The sync equivalent of `ObjectAccessControl::delete`.
@@ -1,3 +1,4 @@
+/// The response of the IAM Credentials `signBlob` method.
 #[derive(serde::Deserialize, Debug)]
 #[serde(untagged)]
 pub enum SignatureResponse {
@@ -8,8 +9,8 @@ pub enum SignatureResponse {
 #[derive(serde::Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SuccessResponse {
-    pub key_id: String,
-    pub signature: String,
+    /// The base64 encoded signature.
+    pub signed_blob: String,
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -22,18 +23,4 @@ pub struct SignatureError {
     pub code: u16,
     pub message: String,
     pub status: String,
-    pub details: Option<Vec<Details>>,
-}
-
-#[derive(serde::Deserialize, Debug)]
-pub struct Details {
-    #[serde(rename = "@type")]
-    pub _type: String,
-    pub links: Vec<Link>,
-}
-
-#[derive(serde::Deserialize, Debug)]
-pub struct Link {
-    pub description: String,
-    pub url: String,
 }
@@ -0,0 +1,154 @@
+use std::time::Duration;
+
+/// Determines how often and how long a `Client` retries requests that fail with a transient error,
+/// such as a connection reset, `429 Too Many Requests` or `503 Service Unavailable`. See
+/// `Error::retryable` for the errors that are retried.
+///
+/// Only requests that are
+/// [idempotent](https://cloud.google.com/storage/docs/retry-strategy#idempotency) are retried.
+/// Reads and listings always are, while requests that modify objects are only idempotent if
+/// `Preconditions` pin the generation (for creating, deleting, composing, copying and rewriting)
+/// or metageneration (for updating) of the object they modify.
+///
+/// Between attempts, the client waits for an exponentially growing backoff with full jitter, so
+/// the wait before the `n`th retry is a random duration between zero and
+/// `min(initial_backoff * multiplier^(n - 1), max_backoff)`.
+/// ### Example
+/// ```no_run
+/// use cloud_storage::{Client, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::new()
+///     .max_attempts(8)
+///     .initial_backoff(Duration::from_millis(500))
+///     .deadline(Duration::from_secs(60));
+/// let client = Client::default().with_retry_policy(policy);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub(crate) max_attempts: u32,
+    pub(crate) initial_backoff: Duration,
+    pub(crate) max_backoff: Duration,
+    pub(crate) multiplier: f64,
+    pub(crate) deadline: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(32),
+            multiplier: 2.0,
+            deadline: Some(Duration::from_secs(120)),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a new instance of `RetryPolicy` with the default settings: at most 4 attempts, a
+    /// backoff starting at 1 second that doubles up to 32 seconds, and a deadline of 2 minutes.
+    /// Equivalent to `RetryPolicy::default()`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self::new().max_attempts(1)
+    }
+
+    /// The maximum number of times a request is sent, including the first attempt. A value of `1`
+    /// disables retries.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// The upper bound of the wait before the first retry.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// The largest upper bound of the wait before any retry.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// The factor by which the upper bound of the wait grows after every retry.
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// The time after the first attempt after which a request is no longer retried. The deadline
+    /// does not interrupt a request that is in progress.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Retry requests regardless of how long ago the first attempt was made.
+    pub fn without_deadline(mut self) -> Self {
+        self.deadline = None;
+        self
+    }
+
+    /// The wait before retrying a request that failed `failures` times, or `None` if it should not
+    /// be retried anymore because it has been attempted `max_attempts` times or the wait would
+    /// exceed the deadline, given that the first attempt was made `elapsed` ago.
+    pub(crate) fn backoff(&self, failures: u32, elapsed: Duration) -> Option<Duration> {
+        if failures == 0 || failures >= self.max_attempts {
+            return None;
+        }
+        let exponent = self.multiplier.powi(failures as i32 - 1);
+        let ceiling =
+            (self.initial_backoff.as_secs_f64() * exponent).min(self.max_backoff.as_secs_f64());
+        let wait = Duration::from_secs_f64(ceiling * jitter());
+        match self.deadline {
+            Some(deadline) if elapsed + wait > deadline => None,
+            _ => Some(wait),
+        }
+    }
+}
+
+// A random factor between 0 and 1 that spreads out the retries of concurrent requests.
+fn jitter() -> f64 {
+    let mut bytes = [0; 4];
+    match openssl::rand::rand_bytes(&mut bytes) {
+        Ok(()) => f64::from(u32::from_be_bytes(bytes)) / f64::from(u32::MAX),
+        Err(_) => 1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_until_max_attempts() {
+        let policy = RetryPolicy::new()
+            .max_attempts(5)
+            .initial_backoff(Duration::from_secs(1))
+            .max_backoff(Duration::from_secs(3))
+            .without_deadline();
+        let zero = Duration::from_secs(0);
+        for (failures, ceiling) in [(1, 1), (2, 2), (3, 3), (4, 3)] {
+            let wait = policy.backoff(failures, zero).unwrap();
+            assert!(wait <= Duration::from_secs(ceiling), "{:?}", wait);
+        }
+        assert_eq!(policy.backoff(5, zero), None);
+        assert_eq!(RetryPolicy::none().backoff(1, zero), None);
+    }
+
+    #[test]
+    fn backoff_respects_deadline() {
+        let policy = RetryPolicy::new()
+            .initial_backoff(Duration::from_secs(0))
+            .deadline(Duration::from_secs(10));
+        assert!(policy.backoff(1, Duration::from_secs(9)).is_some());
+        assert_eq!(policy.backoff(1, Duration::from_secs(11)), None);
+    }
+}
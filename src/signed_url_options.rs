@@ -0,0 +1,124 @@
+/// The request that a signed url is created for. Headers added to these options are bound into the
+/// signature, so the request made with the signed url must send them with exactly these values.
+/// Query parameters are signed as well, and are added to the signed url.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedUrlOptions {
+    pub(crate) method: &'static str,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) query_parameters: Vec<(String, String)>,
+}
+
+impl Default for SignedUrlOptions {
+    fn default() -> Self {
+        Self::get()
+    }
+}
+
+impl SignedUrlOptions {
+    fn with_method(method: &'static str) -> Self {
+        Self {
+            method,
+            headers: Vec::new(),
+            query_parameters: Vec::new(),
+        }
+    }
+
+    /// Options for a url that downloads the object with a `GET` request. Equivalent to
+    /// `SignedUrlOptions::default()`.
+    pub fn get() -> Self {
+        Self::with_method("GET")
+    }
+
+    /// Options for a url that uploads the object with a `PUT` request.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use cloud_storage::SignedUrlOptions;
+    ///
+    /// let opts = SignedUrlOptions::put()
+    ///     .content_type("image/png")
+    ///     .content_length_range(0, 10_000_000);
+    /// ```
+    pub fn put() -> Self {
+        Self::with_method("PUT")
+    }
+
+    /// Options for a url that deletes the object with a `DELETE` request.
+    pub fn delete() -> Self {
+        Self::with_method("DELETE")
+    }
+
+    /// Options for a url that reads the metadata of the object with a `HEAD` request.
+    pub fn head() -> Self {
+        Self::with_method("HEAD")
+    }
+
+    /// Options for a url that starts a resumable upload with a `POST` request. The request must
+    /// send the header `x-goog-resumable: start`, and the `Location` header of the response is the
+    /// session uri to which the object is uploaded.
+    pub fn resumable_upload() -> Self {
+        Self::with_method("POST").header("x-goog-resumable", "start")
+    }
+
+    /// Binds a header into the signature.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use cloud_storage::SignedUrlOptions;
+    ///
+    /// let opts = SignedUrlOptions::put().header("cache-control", "no-cache");
+    /// ```
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers
+            .push((name.to_lowercase(), value.trim().to_string()));
+        self
+    }
+
+    /// Requires the request to send this `content-type`.
+    pub fn content_type(self, content_type: &str) -> Self {
+        self.header("content-type", content_type)
+    }
+
+    /// Requires the request to set this custom metadata on the object, using the
+    /// `x-goog-meta-{key}` header.
+    pub fn metadata(self, key: &str, value: &str) -> Self {
+        self.header(&format!("x-goog-meta-{}", key), value)
+    }
+
+    /// Requires the request to send the `x-goog-content-length-range` header with these bounds,
+    /// which makes Cloud Storage reject uploads whose size in bytes is outside of them.
+    pub fn content_length_range(self, min: u64, max: u64) -> Self {
+        self.header("x-goog-content-length-range", &format!("{},{}", min, max))
+    }
+
+    /// Adds a query parameter to the signed url, which is bound into the signature.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use cloud_storage::SignedUrlOptions;
+    ///
+    /// let opts = SignedUrlOptions::get().query("userProject", "my-project");
+    /// ```
+    pub fn query(mut self, name: &str, value: &str) -> Self {
+        self.query_parameters
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Makes Cloud Storage answer a download with this `Content-Type`, instead of the content
+    /// type of the object.
+    pub fn response_content_type(self, content_type: &str) -> Self {
+        self.query("response-content-type", content_type)
+    }
+
+    /// Makes Cloud Storage answer a download with this `Content-Disposition`, for example
+    /// `attachment; filename="report.pdf"` to make browsers save the object as a file.
+    pub fn response_content_disposition(self, content_disposition: &str) -> Self {
+        self.query("response-content-disposition", content_disposition)
+    }
+
+    /// Addresses this generation of the object, instead of the live one.
+    pub fn generation(self, generation: i64) -> Self {
+        self.query("generation", &generation.to_string())
+    }
+}
@@ -1,15 +1,35 @@
-use crate::error::Error;
+use crate::{error::Error, resources::service_account::ServiceAccount};
 use serde::{Deserialize, Serialize};
+use std::{future::Future, time::Duration};
 
-/// This struct contains contains a token, an expiry, and an access scope.
+/// This struct caches a token together with its expiry, and refreshes it shortly before it
+/// expires.
+///
+/// The cached token is guarded by a lock that is never held across a network call. Refreshes are
+/// serialized by a second lock: while a token is refreshed, callers that still have a valid token
+/// use it without waiting, and callers without one wait for the refresh in flight and share its
+/// result instead of starting their own.
 pub struct Token {
-    // this field contains the JWT and the expiry thereof. They are in the same Option because if
-    // one of them is `Some`, we require that the other be `Some` as well.
-    token: Option<(String, u64)>,
+    // the cached token, the time at which to start refreshing it and the time at which it expires
+    cached: std::sync::Mutex<Option<Cached>>,
+    // held by the caller that is refreshing the token
+    refreshing: tokio::sync::Mutex<()>,
     // store the access scope for later use if we need to refresh the token
     access_scope: String,
+    refresh_margin: u64,
+    clock: fn() -> u64,
 }
 
+#[derive(Clone)]
+struct Cached {
+    token: String,
+    refresh_at: u64,
+    expires_at: u64,
+}
+
+/// How long before it expires a token is refreshed by default.
+pub(crate) const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
 #[derive(Serialize)]
 struct Claims {
     iss: String,
@@ -19,70 +39,248 @@ struct Claims {
     iat: u64,
 }
 
+/// The response of an OAuth2 token endpoint.
 #[derive(Deserialize, Debug)]
-struct TokenResponse {
+pub(crate) struct TokenResponse {
     access_token: String,
-    expires_in: usize,
-    token_type: String,
+    expires_in: u64,
+}
+
+impl TokenResponse {
+    /// Parses the response of a token endpoint, turning unsuccessful responses into an error that
+    /// contains the body, which explains why no token was issued.
+    pub(crate) async fn parse(response: reqwest::Response) -> crate::Result<Self> {
+        let status = response.status();
+        if status.is_success() {
+            Ok(response.json().await?)
+        } else {
+            let body = response.text().await?;
+            Err(Error::Other(format!(
+                "token request failed with {}: {}",
+                status, body
+            )))
+        }
+    }
+
+    /// The access token, and the time at which it expires.
+    pub(crate) fn into_token(self) -> (String, u64) {
+        (self.access_token, now() + self.expires_in)
+    }
 }
 
 impl Token {
     pub fn new(scope: &str) -> Self {
         Self {
-            token: None,
+            cached: std::sync::Mutex::new(None),
+            refreshing: tokio::sync::Mutex::new(()),
             access_scope: scope.to_string(),
+            refresh_margin: DEFAULT_REFRESH_MARGIN.as_secs(),
+            clock: now,
         }
     }
 
-    pub async fn get(&mut self) -> crate::Result<String> {
-        match self.token {
-            Some((ref token, exp)) if exp > now() => Ok(token.clone()),
-            _ => self.retrieve().await,
-        }
+    /// Refreshes the token `margin` before it expires. Tokens that are valid for less than twice
+    /// the margin are refreshed halfway through their lifetime instead.
+    pub fn with_refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin.as_secs();
+        self
     }
 
-    async fn retrieve(&mut self) -> crate::Result<String> {
-        self.token = Some(Self::get_token(&self.access_scope).await?);
-        match self.token {
-            Some(ref token) => Ok(token.0.clone()),
-            None => unreachable!(),
+    /// Requests tokens with `scope` instead.
+    pub fn with_scope(mut self, scope: &str) -> Self {
+        self.access_scope = scope.to_string();
+        self
+    }
+
+    /// Replaces the clock that returns the current time in seconds since the unix epoch.
+    #[cfg(test)]
+    fn with_clock(mut self, clock: fn() -> u64) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    fn cached(&self) -> Option<Cached> {
+        self.cached.lock().unwrap().clone()
+    }
+
+    /// Returns the cached token, or calls `refresh` with the access scope to obtain a new token
+    /// and the time at which it expires, in seconds since the unix epoch. A token that is about to
+    /// expire is returned while another caller refreshes it.
+    pub async fn get_or_refresh<F, Fut>(&self, refresh: F) -> crate::Result<String>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = crate::Result<(String, u64)>>,
+    {
+        let now = (self.clock)();
+        let _guard = match self.cached() {
+            Some(cached) if now < cached.refresh_at => return Ok(cached.token),
+            Some(cached) if now < cached.expires_at => match self.refreshing.try_lock() {
+                Ok(_guard) => {
+                    // refresh early, but keep using the current token if that fails
+                    return Ok(self.refresh(refresh).await.unwrap_or(cached.token));
+                }
+                Err(_) => return Ok(cached.token),
+            },
+            _ => self.refreshing.lock().await,
+        };
+        // another caller may have refreshed the token while we were waiting
+        match self.cached() {
+            Some(cached) if (self.clock)() < cached.expires_at => Ok(cached.token),
+            _ => self.refresh(refresh).await,
         }
     }
 
-    async fn get_token(scope: &str) -> Result<(String, u64), Error> {
+    // Must only be called while holding `self.refreshing`.
+    async fn refresh<F, Fut>(&self, refresh: F) -> crate::Result<String>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = crate::Result<(String, u64)>>,
+    {
+        let (token, expires_at) = refresh(self.access_scope.clone()).await?;
+        let now = (self.clock)();
+        let margin = self.refresh_margin.min(expires_at.saturating_sub(now) / 2);
+        *self.cached.lock().unwrap() = Some(Cached {
+            token: token.clone(),
+            refresh_at: expires_at.saturating_sub(margin),
+            expires_at,
+        });
+        Ok(token)
+    }
+
+    pub async fn get(
+        &self,
+        client: &reqwest::Client,
+        service_account: &ServiceAccount,
+        token_url: &str,
+    ) -> crate::Result<String> {
+        self.get_or_refresh(|scope| async move {
+            Self::get_token(client, service_account, token_url, &scope).await
+        })
+        .await
+    }
+
+    async fn get_token(
+        client: &reqwest::Client,
+        service_account: &ServiceAccount,
+        token_url: &str,
+        scope: &str,
+    ) -> Result<(String, u64), Error> {
         let now = now();
         let exp = now + 3600;
 
         let claims = Claims {
-            iss: crate::SERVICE_ACCOUNT.client_email.clone(),
+            iss: service_account.client_email.clone(),
             scope: scope.into(),
-            aud: "https://www.googleapis.com/oauth2/v4/token".to_string(),
+            aud: token_url.to_string(),
             exp,
             iat: now,
         };
-        let mut header = jsonwebtoken::Header::default();
-        header.alg = jsonwebtoken::Algorithm::RS256;
-        let private_key_bytes = crate::SERVICE_ACCOUNT.private_key.as_bytes();
+        let header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+        let private_key_bytes = service_account.private_key.as_bytes();
         let private_key = jsonwebtoken::EncodingKey::from_rsa_pem(private_key_bytes)?;
         let jwt = jsonwebtoken::encode(&header, &claims, &private_key)?;
         let body = [
             ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
             ("assertion", &jwt),
         ];
-        let response: TokenResponse = super::CLIENT
-            .post("https://www.googleapis.com/oauth2/v4/token")
-            .form(&body)
-            .send()
-            .await?
-            .json()
-            .await?;
-        Ok((response.access_token, exp))
+        let response = client.post(token_url).form(&body).send().await?;
+        Ok(TokenResponse::parse(response).await?.into_token())
     }
 }
 
-fn now() -> u64 {
+/// The current time in seconds since the unix epoch.
+pub(crate) fn now() -> u64 {
     std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+    // Returns a token named after the current refresh count, valid for `lifetime` seconds.
+    async fn issue(
+        refreshes: &AtomicUsize,
+        now: u64,
+        lifetime: u64,
+    ) -> crate::Result<(String, u64)> {
+        let count = refreshes.fetch_add(1, Ordering::SeqCst) + 1;
+        tokio::time::delay_for(Duration::from_millis(10)).await;
+        Ok((format!("token-{}", count), now + lifetime))
+    }
+
+    #[tokio::test]
+    async fn refreshes_before_expiry() -> crate::Result<()> {
+        static NOW: AtomicU64 = AtomicU64::new(1000);
+        let token = Token::new("scope").with_clock(|| NOW.load(Ordering::SeqCst));
+        let refreshes = AtomicUsize::new(0);
+        let get = || token.get_or_refresh(|_| issue(&refreshes, NOW.load(Ordering::SeqCst), 3600));
+
+        assert_eq!(get().await?, "token-1");
+        NOW.store(1000 + 3600 - 301, Ordering::SeqCst);
+        assert_eq!(get().await?, "token-1");
+        NOW.store(1000 + 3600 - 300, Ordering::SeqCst);
+        assert_eq!(get().await?, "token-2");
+        assert_eq!(refreshes.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn short_lived_tokens_are_refreshed_halfway() -> crate::Result<()> {
+        static NOW: AtomicU64 = AtomicU64::new(1000);
+        let token = Token::new("scope")
+            .with_refresh_margin(Duration::from_secs(600))
+            .with_clock(|| NOW.load(Ordering::SeqCst));
+        let refreshes = AtomicUsize::new(0);
+        let get = || token.get_or_refresh(|_| issue(&refreshes, NOW.load(Ordering::SeqCst), 100));
+
+        assert_eq!(get().await?, "token-1");
+        NOW.store(1049, Ordering::SeqCst);
+        assert_eq!(get().await?, "token-1");
+        NOW.store(1050, Ordering::SeqCst);
+        assert_eq!(get().await?, "token-2");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn waiters_share_one_refresh() -> crate::Result<()> {
+        static NOW: AtomicU64 = AtomicU64::new(1000);
+        let token = Token::new("scope").with_clock(|| NOW.load(Ordering::SeqCst));
+        let refreshes = AtomicUsize::new(0);
+        let tokens = futures::future::join_all((0..10).map(|_| {
+            token.get_or_refresh(|_| issue(&refreshes, NOW.load(Ordering::SeqCst), 3600))
+        }))
+        .await;
+
+        assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+        for token in tokens {
+            assert_eq!(token?, "token-1");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn valid_token_is_used_during_refresh() -> crate::Result<()> {
+        static NOW: AtomicU64 = AtomicU64::new(1000);
+        let token = Token::new("scope").with_clock(|| NOW.load(Ordering::SeqCst));
+        let refreshes = AtomicUsize::new(0);
+        let get = || token.get_or_refresh(|_| issue(&refreshes, NOW.load(Ordering::SeqCst), 3600));
+        get().await?;
+
+        NOW.store(1000 + 3600 - 60, Ordering::SeqCst);
+        let (refreshed, waiting) = futures::future::join(get(), get()).await;
+        assert_eq!(refreshed?, "token-2");
+        assert_eq!(waiting?, "token-1");
+
+        // a failed early refresh leaves the current token in place
+        NOW.store(2000 + 3600 - 60, Ordering::SeqCst);
+        let failed = token
+            .get_or_refresh(|_| async { Err(crate::Error::new("unavailable")) })
+            .await;
+        assert_eq!(failed?, "token-2");
+        Ok(())
+    }
+}